@@ -1,35 +1,80 @@
-use obd2::commands::Obd2DataRetrieval;
+use obd2::{commands::Obd2DataRetrieval, Obd2Device};
 
 use std::time;
 
 fn main() {
     env_logger::init();
-    let mut device: obd2::Obd2<obd2::device::Elm327> = obd2::Obd2::default();
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/dev/ttyUSB0".to_owned());
+    let mut device = obd2::Obd2::new(obd2::device::Elm327::from_serial_port(&path, 38400).unwrap());
 
     println!("VIN: {:?}", device.get_vin());
-    for s in device.get_service_1_pid_support_1().unwrap().iter() {
-        println!("PID support ($01-$20): {:08X}", s);
-    }
-    for s in device.get_service_1_pid_support_2().unwrap().iter() {
-        println!("PID support ($21-$40): {:08X}", s);
+    println!("Calibration IDs: {:?}", device.get_calibration_ids());
+    println!(
+        "Calibration verification numbers: {:08X?}",
+        device.get_calibration_verification_numbers()
+    );
+    println!("ECU names: {:?}", device.get_ecu_name());
+    for (address, pids) in device.supported_pids().unwrap() {
+        println!(
+            "PIDs supported by ECU {}: {:02X?}",
+            address,
+            pids.iter().collect::<Vec<_>>()
+        );
     }
 
-    println!("DTC Info: {:#?}", device.get_dtc_info());
+    let info = device.get_dtc_info_by_ecu();
+    println!("DTC Info: {:#?}", info);
+    if let Ok(info) = info {
+        for (address, info) in info.iter() {
+            println!(
+                "Readiness of ECU {}: {} (incomplete: {:?})",
+                address,
+                if info.readiness.is_ready(1) {
+                    "ready"
+                } else {
+                    "not ready"
+                },
+                info.readiness.incomplete().collect::<Vec<_>>()
+            );
+        }
+    }
 
-    let dtcs = device.get_dtcs();
+    let dtcs = device.get_dtc_report();
     println!("DTCs: {:?}", dtcs);
     if let Ok(dtcs) = dtcs {
-        for (i, response) in dtcs.iter().enumerate() {
-            println!("DTCs from response {}:", i);
-            for dtc in response {
-                println!("  - {}", dtc);
+        for (address, report) in dtcs.iter() {
+            println!("DTCs from ECU {}:", address);
+            for (status, dtc) in report.iter() {
+                println!("  - {} ({:?})", dtc, status);
+            }
+        }
+    }
+
+    println!("Freeze frame: {:02X?}", device.freeze_frame(0).dump());
+
+    if let Ok(results) = device.get_monitor_test_results() {
+        for (address, results) in results.iter() {
+            println!("Monitor tests from ECU {}:", address);
+            for r in results {
+                println!(
+                    "  - {} TID {:02X}: {} {} ({} to {}) {}",
+                    r.name().unwrap_or_else(|| format!("OBDMID {:02X}", r.mid)),
+                    r.tid,
+                    r.value,
+                    r.unit,
+                    r.min,
+                    r.max,
+                    if r.passed { "passed" } else { "FAILED" }
+                );
             }
         }
     }
 
     let state = time::Instant::now();
     while state.elapsed() < time::Duration::from_secs(5) {
-        println!("");
+        println!();
         println!(
             "Coolant Temperature: {:?}",
             device.get_engine_coolant_temperature()
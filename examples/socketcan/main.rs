@@ -0,0 +1,84 @@
+//! Query a scripted ECU over SocketCAN
+//!
+//! A thread pretends to be an engine ECU on the given interface (default `vcan0`), so this can be
+//! run without a vehicle. Create the interface first:
+//!
+//! ```sh
+//! sudo ip link add dev vcan0 type vcan
+//! sudo ip link set up vcan0
+//! ```
+
+use obd2::commands::Obd2DataRetrieval;
+use socketcan::{CanFrame, CanSocket, EmbeddedFrame, Id, Socket, StandardId};
+
+use std::{thread, time};
+
+const VIN: &[u8; 17] = b"1D4GP00R55B123456";
+
+fn send(socket: &CanSocket, data: &[u8]) {
+    let mut padded = [0u8; 8];
+    padded[..data.len()].copy_from_slice(data);
+    let frame = CanFrame::new(StandardId::new(0x7E8).unwrap(), &padded).unwrap();
+    socket.write_frame_insist(&frame).unwrap();
+}
+
+fn ecu(interface: &str) {
+    let socket = CanSocket::open(interface).unwrap();
+    loop {
+        let Ok(CanFrame::Data(frame)) = socket.read_frame() else {
+            continue;
+        };
+        if frame.id() != Id::Standard(StandardId::new(0x7DF).unwrap()) {
+            continue;
+        }
+
+        match frame.data() {
+            [0x02, 0x01, 0x00, ..] => send(&socket, &[0x06, 0x41, 0x00, 0x18, 0x18, 0x00, 0x00]),
+            [0x02, 0x01, 0x0C, ..] => send(&socket, &[0x04, 0x41, 0x0C, 0x1A, 0xF8]),
+            [0x02, 0x01, 0x0D, ..] => send(&socket, &[0x03, 0x41, 0x0D, 0x32]),
+            [0x02, 0x09, 0x02, ..] => {
+                let mut message = vec![0x49, 0x02, 0x01];
+                message.extend_from_slice(VIN);
+
+                send(
+                    &socket,
+                    &[&[0x10, message.len() as u8], &message[..6]].concat(),
+                );
+
+                // wait for flow control
+                loop {
+                    if let Ok(CanFrame::Data(frame)) = socket.read_frame() {
+                        if frame.id() == Id::Standard(StandardId::new(0x7E0).unwrap())
+                            && frame.data().first() == Some(&0x30)
+                        {
+                            break;
+                        }
+                    }
+                }
+
+                for (i, chunk) in message[6..].chunks(7).enumerate() {
+                    send(&socket, &[&[0x21 + i as u8], chunk].concat());
+                }
+            }
+            _ => send(&socket, &[0x03, 0x7F, frame.data()[1], 0x11]),
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let interface = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "vcan0".to_owned());
+
+    {
+        let interface = interface.clone();
+        thread::spawn(move || ecu(&interface));
+    }
+    thread::sleep(time::Duration::from_millis(100));
+
+    let mut device = obd2::Obd2::new(obd2::device::SocketCan::new(&interface).unwrap());
+    println!("VIN: {:?}", device.get_vin());
+    println!("RPM: {:?}", device.get_rpm());
+    println!("Speed (km/h): {:?}", device.get_speed());
+}
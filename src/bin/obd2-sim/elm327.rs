@@ -0,0 +1,302 @@
+use crate::vehicle::{Response, Vehicle};
+
+/// The identification string of the emulated device
+const IDENTIFIER: &str = "ELM327 v1.5";
+
+/// The only protocol that can be emulated: ISO 15765-4 CAN (11 bit ID, 500 kbaud)
+const CAN_11_500: u8 = 6;
+
+/// An emulated ELM327, turning command lines into the text the device would send back
+pub struct Elm327 {
+    echo: bool,
+    headers: bool,
+    spaces: bool,
+    linefeeds: bool,
+    automatic_protocol: bool,
+    protocol: u8,
+    connected: bool,
+    awaiting_baud_rate_confirmation: bool,
+    last_command: Option<String>,
+}
+
+impl Default for Elm327 {
+    fn default() -> Self {
+        Elm327 {
+            echo: true,
+            headers: false,
+            spaces: true,
+            linefeeds: false,
+            automatic_protocol: true,
+            protocol: 0,
+            connected: false,
+            awaiting_baud_rate_confirmation: false,
+            last_command: None,
+        }
+    }
+}
+
+impl Elm327 {
+    /// Handle one line of input (without the carriage return), returning everything the device
+    /// sends in reply up to and including the prompt
+    pub fn handle_line(&mut self, line: &str, vehicle: &mut Vehicle) -> String {
+        if self.awaiting_baud_rate_confirmation {
+            self.awaiting_baud_rate_confirmation = false;
+            return if line.is_empty() {
+                self.reply(&["OK"])
+            } else {
+                self.prompt()
+            };
+        }
+
+        let mut output = String::new();
+        if self.echo {
+            output.push_str(line);
+            output.push_str(self.eol());
+        }
+
+        // commands are always ASCII, so anything else cannot be understood
+        if !line.is_ascii() {
+            output.push_str(&self.reply(&["?"]));
+            return output;
+        }
+
+        let mut command: String = line
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        if command.is_empty() {
+            // an empty line repeats the last command
+            match &self.last_command {
+                Some(last) => command = last.clone(),
+                None => {
+                    output.push_str(&self.reply(&["?"]));
+                    return output;
+                }
+            }
+        }
+
+        let reply = match command.strip_prefix("AT") {
+            Some(at) => self.at_command(at),
+            None => {
+                self.last_command = Some(command.clone());
+                self.obd_command(&command, vehicle)
+            }
+        };
+        output.push_str(&reply);
+        output
+    }
+
+    fn at_command(&mut self, command: &str) -> String {
+        let flag = |c: &str| match c {
+            "0" => Some(false),
+            "1" => Some(true),
+            _ => None,
+        };
+
+        if let Some(rate) = command.strip_prefix("BRD") {
+            if u8::from_str_radix(rate, 16).is_ok_and(|d| d > 0) {
+                // on a pseudo-terminal the baud rate does not matter, so always accept it
+                self.awaiting_baud_rate_confirmation = true;
+                return format!("OK{0}{1}{0}", self.eol(), IDENTIFIER);
+            }
+            return self.reply(&["?"]);
+        }
+
+        // two-letter commands first, so that `SP` is not taken for `S` with an argument
+        let (name, argument) = match command.get(..2) {
+            Some(
+                "@1" | "AL" | "AT" | "CA" | "DP" | "M0" | "M1" | "NL" | "PC" | "RV" | "SP" | "ST"
+                | "TP" | "WS",
+            ) => command.split_at(2),
+            _ => command.split_at(command.len().min(1)),
+        };
+
+        match (name, argument) {
+            ("Z", "") => {
+                *self = Elm327::default();
+                format!("{0}{0}{1}{0}{0}>", self.eol(), IDENTIFIER)
+            }
+            ("I", "") => self.reply(&[IDENTIFIER]),
+            ("@1", "") => self.reply(&["OBDII to RS232 Interpreter"]),
+            ("RV", "") => self.reply(&["12.6V"]),
+            ("DP", "N") => {
+                let automatic = if self.automatic_protocol { "A" } else { "" };
+                self.reply(&[&format!("{}{:X}", automatic, self.protocol)])
+            }
+            ("DP", "") => {
+                let description = match self.protocol {
+                    0 => "AUTO".to_owned(),
+                    CAN_11_500 if self.automatic_protocol => {
+                        "AUTO, ISO 15765-4 (CAN 11/500)".to_owned()
+                    }
+                    CAN_11_500 => "ISO 15765-4 (CAN 11/500)".to_owned(),
+                    p => format!("PROTOCOL {:X}", p),
+                };
+                self.reply(&[&description])
+            }
+            ("E", a) | ("H", a) | ("S", a) | ("L", a) => match flag(a) {
+                Some(value) => {
+                    match name {
+                        "E" => self.echo = value,
+                        "H" => self.headers = value,
+                        "S" => self.spaces = value,
+                        _ => self.linefeeds = value,
+                    }
+                    self.reply(&["OK"])
+                }
+                None => self.reply(&["?"]),
+            },
+            ("SP", a) | ("TP", a) => {
+                let (automatic, protocol) = match a.strip_prefix('A') {
+                    Some(p) => (true, p),
+                    None => (false, a),
+                };
+                match u8::from_str_radix(protocol, 16) {
+                    Ok(p) if p <= 0xC => {
+                        self.automatic_protocol = automatic || p == 0;
+                        self.protocol = p;
+                        self.connected = false;
+                        self.reply(&["OK"])
+                    }
+                    _ => self.reply(&["?"]),
+                }
+            }
+            // settings that are accepted but have no effect on the emulation
+            ("AT", _)
+            | ("ST", _)
+            | ("CA", _)
+            | ("AL", "")
+            | ("NL", "")
+            | ("PC", "")
+            | ("D", "")
+            | ("WS", "")
+            | ("M0", "")
+            | ("M1", "") => self.reply(&["OK"]),
+            _ => self.reply(&["?"]),
+        }
+    }
+
+    fn obd_command(&mut self, command: &str, vehicle: &mut Vehicle) -> String {
+        // a trailing single digit gives the number of responses to wait for, which is ignored
+        let hex = &command[..command.len() - command.len() % 2];
+        let request: Option<Vec<u8>> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect();
+        let request = match request {
+            Some(r) if !r.is_empty() && r.len() <= 7 => r,
+            _ => return self.reply(&["?"]),
+        };
+
+        let mut lines = Vec::new();
+        if !self.connected {
+            if self.automatic_protocol {
+                lines.push("SEARCHING...".to_owned());
+                self.protocol = CAN_11_500;
+            }
+            if self.protocol != CAN_11_500 {
+                lines.push("UNABLE TO CONNECT".to_owned());
+                return self.reply_lines(lines);
+            }
+            self.connected = true;
+        }
+
+        let responses = vehicle.respond(&request);
+        if responses.is_empty() {
+            lines.push("NO DATA".to_owned());
+        }
+        for response in responses {
+            self.format_response(&response, &mut lines);
+        }
+        self.reply_lines(lines)
+    }
+
+    /// Format a response the way the ELM327 shows CAN messages
+    fn format_response(&self, response: &Response, lines: &mut Vec<String>) {
+        let data = &response.data;
+        let header = format!("{:03X}", response.id);
+
+        if data.len() <= 7 {
+            let mut frame = vec![data.len() as u8];
+            frame.extend(data);
+            lines.push(self.format_frame(&header, &frame));
+            return;
+        }
+
+        if self.headers {
+            // show every frame, including the protocol control information
+            let mut frame = vec![0x10 | (data.len() >> 8) as u8, data.len() as u8];
+            frame.extend(&data[..6]);
+            lines.push(self.format_frame(&header, &frame));
+            for (i, chunk) in data[6..].chunks(7).enumerate() {
+                let mut frame = vec![0x20 | ((i + 1) % 0x10) as u8];
+                frame.extend(chunk);
+                lines.push(self.format_frame(&header, &frame));
+            }
+        } else {
+            // the message length, then each line of data with its index
+            lines.push(format!("{:03X}", data.len()));
+            let first = std::iter::once(&data[..6]);
+            for (i, chunk) in first.chain(data[6..].chunks(7)).enumerate() {
+                lines.push(format!(
+                    "{:X}:{}{}",
+                    i % 0x10,
+                    self.separator(),
+                    self.format_bytes(chunk)
+                ));
+            }
+        }
+    }
+
+    /// Format a CAN frame; without headers, the protocol control information is not shown
+    fn format_frame(&self, header: &str, frame: &[u8]) -> String {
+        if self.headers {
+            format!("{}{}{}", header, self.separator(), self.format_bytes(frame))
+        } else {
+            self.format_bytes(&frame[1..])
+        }
+    }
+
+    fn format_bytes(&self, bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(self.separator())
+    }
+
+    fn separator(&self) -> &'static str {
+        if self.spaces {
+            " "
+        } else {
+            ""
+        }
+    }
+
+    fn reply(&self, lines: &[&str]) -> String {
+        self.reply_lines(lines.iter().map(|l| l.to_string()).collect())
+    }
+
+    fn reply_lines(&self, lines: Vec<String>) -> String {
+        let mut output = String::new();
+        for line in lines {
+            output.push_str(&line);
+            output.push_str(self.eol());
+        }
+        output.push_str(&self.prompt());
+        output
+    }
+
+    fn prompt(&self) -> String {
+        format!("{}>", self.eol())
+    }
+
+    fn eol(&self) -> &'static str {
+        if self.linefeeds {
+            "\r\n"
+        } else {
+            "\r"
+        }
+    }
+}
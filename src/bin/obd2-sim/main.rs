@@ -0,0 +1,135 @@
+//! ELM327 emulator on a pseudo-terminal
+//!
+//! Opens a pseudo-terminal that behaves like an ELM327 v1.5 connected to a virtual vehicle, so
+//! that [obd2::device::Elm327] can be used without any hardware. The path of the terminal is
+//! printed on startup, and can also be linked to a fixed path with `--link`.
+//!
+//! ```sh
+//! obd2-sim --vin 1D4GP00R55B123456 --dtc P0420 --dtc U0100 --pending P0171 --link /tmp/elm327
+//! ```
+
+#![forbid(unsafe_code)]
+
+mod elm327;
+mod vehicle;
+
+use nix::{pty, sys::termios, unistd};
+use obd2::commands::Dtc;
+use std::{
+    fs,
+    io::{Read, Write},
+    path::PathBuf,
+    process,
+};
+
+const USAGE: &str = "usage: obd2-sim [--vin VIN] [--dtc CODE]... [--pending CODE]... [--link PATH]";
+
+struct Options {
+    vin: String,
+    dtcs: Vec<u16>,
+    pending_dtcs: Vec<u16>,
+    link: Option<PathBuf>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        vin: "1D4GP00R55B123456".to_owned(),
+        dtcs: Vec::new(),
+        pending_dtcs: Vec::new(),
+        link: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--vin" => {
+                let vin = value()?;
+                if vin.len() != 17 || !vin.is_ascii() {
+                    return Err(format!("invalid VIN {:?}", vin));
+                }
+                options.vin = vin;
+            }
+            "--dtc" => {
+                let dtc: Dtc = value()?.parse().map_err(|e| format!("{}", e))?;
+                options.dtcs.push(dtc.into());
+            }
+            "--pending" => {
+                let dtc: Dtc = value()?.parse().map_err(|e| format!("{}", e))?;
+                options.pending_dtcs.push(dtc.into());
+            }
+            "--link" => options.link = Some(value()?.into()),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let pty = pty::openpty(None, None)?;
+
+    // the client expects a raw serial port, without any line editing or echo
+    let mut attributes = termios::tcgetattr(&pty.slave)?;
+    termios::cfmakeraw(&mut attributes);
+    termios::tcsetattr(&pty.slave, termios::SetArg::TCSANOW, &attributes)?;
+
+    let path = unistd::ttyname(&pty.slave)?;
+    if let Some(link) = &options.link {
+        if fs::symlink_metadata(link).is_ok() {
+            fs::remove_file(link)?;
+        }
+        std::os::unix::fs::symlink(&path, link)?;
+        println!(
+            "obd2-sim: listening on {} ({})",
+            link.display(),
+            path.display()
+        );
+    } else {
+        println!("obd2-sim: listening on {}", path.display());
+    }
+
+    let mut vehicle = vehicle::Vehicle::new(options.vin, options.dtcs, options.pending_dtcs);
+    let mut elm327 = elm327::Elm327::default();
+
+    // the slave end stays open so that reads do not fail while no client is connected
+    let _slave = pty.slave;
+    let mut terminal = fs::File::from(pty.master);
+    let mut line = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let len = terminal.read(&mut buf)?;
+        for b in &buf[..len] {
+            match b {
+                b'\r' => {
+                    let command = String::from_utf8_lossy(&line).into_owned();
+                    line.clear();
+                    let reply = elm327.handle_line(&command, &mut vehicle);
+                    terminal.write_all(reply.as_bytes())?;
+                }
+                // line feeds and other control characters are ignored
+                b if b.is_ascii_control() => (),
+                b => line.push(*b),
+            }
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("obd2-sim: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options) {
+        eprintln!("obd2-sim: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,249 @@
+use std::time;
+
+/// The response address of the engine control module
+const ECM: u16 = 0x7E8;
+
+/// The response address of the transmission control module
+const TCM: u16 = 0x7E9;
+
+/// Service 1 PIDs supported by the engine control module
+const ECM_SERVICE_1_PIDS: &[u8] = &[0x01, 0x04, 0x05, 0x0C, 0x0D, 0x0F, 0x11, 0x1F, 0x20, 0x2F];
+
+/// Service 1 PIDs supported by the transmission control module
+const TCM_SERVICE_1_PIDS: &[u8] = &[0x01];
+
+/// Service 1 PIDs stored in the freeze frame of the engine control module
+const FREEZE_FRAME_PIDS: &[u8] = &[0x02, 0x04, 0x05, 0x0C, 0x0D, 0x11];
+
+/// Service 6 OBDMIDs supported by the engine control module
+const ECM_SERVICE_6_MIDS: &[u8] = &[0x01, 0x20, 0x21];
+
+/// Service 8 TIDs supported by the engine control module
+const ECM_SERVICE_8_TIDS: &[u8] = &[0x01];
+
+/// Service 9 InfoTypes supported by the engine control module
+const ECM_SERVICE_9_INFO_TYPES: &[u8] = &[0x02, 0x04, 0x06, 0x0A];
+
+/// A reply from one ECU
+pub struct Response {
+    /// The CAN ID the ECU responds with
+    pub id: u16,
+    /// The reply, starting with the service byte
+    pub data: Vec<u8>,
+}
+
+/// A virtual vehicle with an engine and a transmission control module
+///
+/// Sensor values are derived from the time since the vehicle was created, so they change while
+/// the simulator is running.
+pub struct Vehicle {
+    vin: String,
+    dtcs: Vec<u16>,
+    pending_dtcs: Vec<u16>,
+    permanent_dtcs: Vec<u16>,
+    freeze_frame_dtc: Option<u16>,
+    start: time::Instant,
+}
+
+impl Vehicle {
+    /// Create a vehicle whose engine control module has the given confirmed and pending DTCs
+    ///
+    /// The confirmed DTCs are also stored as permanent DTCs, which clearing does not remove. If
+    /// there are confirmed DTCs, a freeze frame is stored for the first one.
+    pub fn new(vin: String, dtcs: Vec<u16>, pending_dtcs: Vec<u16>) -> Self {
+        Vehicle {
+            vin,
+            permanent_dtcs: dtcs.clone(),
+            freeze_frame_dtc: dtcs.first().copied(),
+            dtcs,
+            pending_dtcs,
+            start: time::Instant::now(),
+        }
+    }
+
+    /// Get every ECU's reply to an OBD-II request
+    pub fn respond(&mut self, request: &[u8]) -> Vec<Response> {
+        let mut responses = Vec::new();
+        for id in [ECM, TCM] {
+            if let Some(data) = self.respond_as(id, request) {
+                responses.push(Response { id, data });
+            }
+        }
+        responses
+    }
+
+    fn respond_as(&mut self, id: u16, request: &[u8]) -> Option<Vec<u8>> {
+        let service = *request.first()?;
+        let data = match (service, request.get(1)) {
+            (0x01, Some(&pid)) => self.service_1(id, pid)?,
+            (0x02, Some(&pid)) if id == ECM && request.get(2) == Some(&0) => {
+                let mut data = vec![0];
+                data.extend(self.service_2(pid)?);
+                data
+            }
+            (0x03, None) => dtc_list(id, &self.dtcs),
+            (0x04, None) => {
+                if id == ECM {
+                    self.dtcs.clear();
+                    self.pending_dtcs.clear();
+                    self.freeze_frame_dtc = None;
+                }
+                Vec::new()
+            }
+            (0x06, Some(&mid)) if id == ECM => self.service_6(mid)?,
+            (0x07, None) => dtc_list(id, &self.pending_dtcs),
+            (0x08, Some(&tid)) if id == ECM => self.service_8(tid)?,
+            (0x0A, None) => dtc_list(id, &self.permanent_dtcs),
+            (0x09, Some(&info_type)) if id == ECM => self.service_9(info_type)?,
+            _ => return None,
+        };
+
+        let mut response = vec![0x40 | service];
+        response.extend(request.get(1));
+        response.extend(data);
+        Some(response)
+    }
+
+    fn service_1(&self, id: u16, pid: u8) -> Option<Vec<u8>> {
+        let supported = if id == ECM {
+            ECM_SERVICE_1_PIDS
+        } else {
+            TCM_SERVICE_1_PIDS
+        };
+        if pid.is_multiple_of(0x20) {
+            return support_bitmap(supported, pid);
+        }
+        if !supported.contains(&pid) {
+            return None;
+        }
+
+        self.pid_value(id, pid, self.start.elapsed().as_secs_f32())
+    }
+
+    /// Get the freeze frame, which was stored when the vehicle was started
+    fn service_2(&self, pid: u8) -> Option<Vec<u8>> {
+        let dtc = self.freeze_frame_dtc?;
+        if pid.is_multiple_of(0x20) {
+            return support_bitmap(FREEZE_FRAME_PIDS, pid);
+        }
+        if !FREEZE_FRAME_PIDS.contains(&pid) {
+            return None;
+        }
+
+        match pid {
+            0x02 => Some(dtc.to_be_bytes().to_vec()),
+            _ => self.pid_value(ECM, pid, 0.),
+        }
+    }
+
+    /// Get the value of a service 1 PID `t` seconds after the vehicle was started
+    fn pid_value(&self, id: u16, pid: u8, t: f32) -> Option<Vec<u8>> {
+        Some(match pid {
+            0x01 => {
+                let dtc_count = if id == ECM { self.dtcs.len() as u8 } else { 0 };
+                let mil = if dtc_count > 0 { 0x80 } else { 0 };
+                vec![mil | dtc_count.min(0x7f), 0x07, 0x65, 0x00]
+            }
+            0x04 => vec![(64. + 64. * (t * 0.3).sin()) as u8],
+            // warm up from 20 ºC to 90 ºC over the first 70 seconds
+            0x05 => vec![(20. + t.min(70.) + 40.) as u8],
+            0x0C => {
+                let rpm = 800. + 1500. * (t * 0.2).sin().abs();
+                ((rpm * 4.) as u16).to_be_bytes().to_vec()
+            }
+            0x0D => vec![(100. * (t * 0.05).sin().abs()) as u8],
+            0x0F => vec![25 + 40],
+            0x11 => vec![(40. + 30. * (t * 0.3).sin()) as u8],
+            0x1F => (t.min(f32::from(u16::MAX)) as u16).to_be_bytes().to_vec(),
+            // slowly use up fuel
+            0x2F => vec![(191. - (t / 60.).min(191.)) as u8],
+            _ => return None,
+        })
+    }
+
+    /// Get the on-board monitor test results
+    ///
+    /// The catalyst monitor fails while P0420 is a confirmed DTC.
+    fn service_6(&self, mid: u8) -> Option<Vec<u8>> {
+        if mid.is_multiple_of(0x20) {
+            return support_bitmap(ECM_SERVICE_6_MIDS, mid);
+        }
+
+        // each test: OBDMID, TID, Unit and Scaling ID, value, minimum, maximum
+        let tests: &[(u8, u8, u16, u16, u16)] = match mid {
+            // oxygen sensor rich to lean threshold voltage, and switch time
+            0x01 => &[(0x01, 0x0A, 3400, 0, 8000), (0x05, 0x10, 100, 0, 200)],
+            0x21 if self.dtcs.contains(&0x0420) => &[(0x80, 0x01, 50, 100, 0xFFFF)],
+            0x21 => &[(0x80, 0x01, 154, 100, 0xFFFF)],
+            _ => return None,
+        };
+
+        let mut data = Vec::new();
+        for (tid, unit_and_scaling, value, min, max) in tests {
+            data.extend([mid, *tid, *unit_and_scaling]);
+            data.extend(value.to_be_bytes());
+            data.extend(min.to_be_bytes());
+            data.extend(max.to_be_bytes());
+        }
+        // the OBDMID of the first test is the one after the service
+        data.remove(0);
+        Some(data)
+    }
+
+    /// Run an on-board control test; sealing the EVAP system is accepted but does nothing
+    fn service_8(&self, tid: u8) -> Option<Vec<u8>> {
+        if tid.is_multiple_of(0x20) {
+            return support_bitmap(ECM_SERVICE_8_TIDS, tid);
+        }
+        ECM_SERVICE_8_TIDS.contains(&tid).then(Vec::new)
+    }
+
+    fn service_9(&self, info_type: u8) -> Option<Vec<u8>> {
+        if info_type.is_multiple_of(0x20) {
+            return support_bitmap(ECM_SERVICE_9_INFO_TYPES, info_type);
+        }
+
+        match info_type {
+            0x02 => {
+                let mut data = vec![0x01];
+                data.extend(self.vin.as_bytes());
+                Some(data)
+            }
+            // calibration ID, padded to 16 characters
+            0x04 => {
+                let mut data = vec![0x01];
+                data.extend(b"OBD2SIM00000001\0");
+                Some(data)
+            }
+            // calibration verification number
+            0x06 => Some(vec![0x01, 0x1A, 0x2B, 0x3C, 0x4D]),
+            // ECU name, padded to 20 characters
+            0x0A => {
+                let mut data = vec![0x01];
+                data.extend(b"ECM\0-EngineControl\0\0");
+                Some(data)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Encode a list of DTCs in the CAN format; only the engine control module has DTCs
+fn dtc_list(id: u16, dtcs: &[u16]) -> Vec<u8> {
+    let dtcs: &[u16] = if id == ECM { dtcs } else { &[] };
+    let mut data = vec![dtcs.len() as u8];
+    for dtc in dtcs {
+        data.extend(dtc.to_be_bytes());
+    }
+    data
+}
+
+/// Build the bitmap of supported PIDs after `base`, or `None` if none are supported
+fn support_bitmap(supported: &[u8], base: u8) -> Option<Vec<u8>> {
+    let bitmap = supported
+        .iter()
+        .filter(|pid| **pid > base && u16::from(**pid) <= u16::from(base) + 0x20)
+        .fold(0u32, |bitmap, pid| bitmap | (1 << (0x20 - (pid - base))));
+
+    (base == 0 || bitmap != 0).then(|| bitmap.to_be_bytes().to_vec())
+}
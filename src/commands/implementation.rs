@@ -1,71 +1,466 @@
-use crate::{Error, Obd2Device, Result};
+use log::debug;
+use std::collections::BTreeMap;
 
-use super::{Dtc, DtcsInfo, OxygenSensorData};
+use crate::{EcuAddress, Error, Obd2Device, Result};
+
+use super::{
+    DieselExhaustFluidSensor, Dtc, DtcReport, DtcStatus, DtcsInfo, EnginePercentTorque, FuelRates,
+    InUsePerformanceTracking, MaximumValues, MonitorTestResult, OxygenSensorData,
+    OxygenSensorLocation, OxygenSensorTestResult, Readiness, ScrInducementSystem, SupportedPids,
+    WideRangeOxygenSensorCurrent, WideRangeOxygenSensorVoltage,
+};
 
 pub(super) fn get_vin<T: Obd2Device>(device: &mut T) -> Result<String> {
-    let mut result = device.obd_command(0x09, 0x02)?.pop().unwrap();
-    result.remove(0); // do not know what this byte is
-    Ok(String::from_utf8(result)?)
+    get_vehicle_info_string(device, 0x02)?
+        .into_values()
+        .next()
+        .ok_or_else(|| Error::Other("get_vin: no responses".to_owned()))
+}
+
+/// Get the supported IDs of a service, without the IDs of the bitmaps that list them
+pub(super) fn get_supported_data_ids<T: Obd2Device>(
+    device: &mut T,
+    service: u8,
+) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+    let mut supported = get_supported_ids(device, service)?;
+    for ids in supported.values_mut() {
+        ids.retain(|id| !id.is_multiple_of(0x20));
+    }
+    Ok(supported)
+}
+
+/// The data of a service 09 InfoType from one ECU
+struct VehicleInfo {
+    /// The number of data items, only sent on CAN
+    count: Option<u8>,
+    data: Vec<u8>,
+}
+
+/// Request a service 09 InfoType and get the data of each ECU
+///
+/// On CAN, the data starts with the number of data items. Other protocols instead send the data
+/// in numbered messages of four bytes each, and the numbers are checked and removed.
+fn get_vehicle_info<T: Obd2Device>(
+    device: &mut T,
+    info_type: u8,
+) -> Result<BTreeMap<EcuAddress, VehicleInfo>> {
+    device
+        .obd_command_by_ecu(0x09, info_type)?
+        .into_iter()
+        .map(|(address, data)| {
+            if !matches!(address, EcuAddress::Legacy(_)) {
+                return match data.split_first() {
+                    Some((&count, data)) => Ok((
+                        address,
+                        VehicleInfo {
+                            count: Some(count),
+                            data: data.to_vec(),
+                        },
+                    )),
+                    None => Err(Error::UnexpectedFrame("empty vehicle information", data)),
+                };
+            }
+
+            let mut joined = Vec::new();
+            for (i, message) in data.chunks(5).enumerate() {
+                if message.len() != 5 || usize::from(message[0]) != i + 1 {
+                    return Err(Error::UnexpectedFrame(
+                        "vehicle information message number",
+                        data,
+                    ));
+                }
+                joined.extend_from_slice(&message[1..]);
+            }
+            Ok((
+                address,
+                VehicleInfo {
+                    count: None,
+                    data: joined,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Get a list of fixed length data items from service 09
+fn get_vehicle_info_items<T: Obd2Device>(
+    device: &mut T,
+    info_type: u8,
+    item_len: usize,
+) -> Result<BTreeMap<EcuAddress, Vec<Vec<u8>>>> {
+    get_vehicle_info(device, info_type)?
+        .into_iter()
+        .map(|(address, VehicleInfo { count, data })| {
+            let expected = match count {
+                Some(count) => data.len() == usize::from(count) * item_len,
+                None => data.len().is_multiple_of(item_len),
+            };
+            if !expected {
+                return Err(Error::UnexpectedFrame("vehicle information length", data));
+            }
+            Ok((address, data.chunks(item_len).map(<[u8]>::to_vec).collect()))
+        })
+        .collect()
+}
+
+/// Get a text from service 09, without the padding
+///
+/// Texts are padded with zeros: at the start of a VIN sent in legacy messages, at the end of
+/// other texts, and in the middle of an ECU name, after the abbreviation.
+pub(super) fn get_vehicle_info_string<T: Obd2Device>(
+    device: &mut T,
+    info_type: u8,
+) -> Result<BTreeMap<EcuAddress, String>> {
+    get_vehicle_info(device, info_type)?
+        .into_iter()
+        .map(|(address, info)| {
+            let text = String::from_utf8(info.data)?;
+            Ok((address, text.replace('\0', "")))
+        })
+        .collect()
+}
+
+pub(super) fn get_calibration_ids<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, Vec<String>>> {
+    get_vehicle_info_items(device, 0x04, 16)?
+        .into_iter()
+        .map(|(address, ids)| {
+            let ids = ids
+                .into_iter()
+                .map(|id| Ok(String::from_utf8(id)?.trim_end_matches('\0').to_owned()))
+                .collect::<Result<_>>()?;
+            Ok((address, ids))
+        })
+        .collect()
+}
+
+pub(super) fn get_calibration_verification_numbers<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, Vec<u32>>> {
+    Ok(get_vehicle_info_items(device, 0x06, 4)?
+        .into_iter()
+        .map(|(address, cvns)| {
+            let cvns = cvns
+                .into_iter()
+                .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                .collect();
+            (address, cvns)
+        })
+        .collect())
+}
+
+pub(super) fn get_dtc_report<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, DtcReport>> {
+    let mut report: BTreeMap<EcuAddress, DtcReport> = BTreeMap::new();
+
+    for (service, status) in [
+        (0x03, DtcStatus::Confirmed),
+        (0x07, DtcStatus::Pending),
+        (0x0A, DtcStatus::Permanent),
+    ] {
+        let dtcs = match <Vec<Dtc>>::get_obd2_val_mode(device, service) {
+            Ok(dtcs) => dtcs,
+            Err(e) if e.is_unsupported() => {
+                debug!(
+                    "get_dtc_report: service {:02X} not supported: {}",
+                    service, e
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        for (address, dtcs) in dtcs {
+            let entry = report.entry(address).or_default();
+            match status {
+                DtcStatus::Confirmed => entry.confirmed = dtcs,
+                DtcStatus::Pending => entry.pending = dtcs,
+                DtcStatus::Permanent => entry.permanent = dtcs,
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Walk the chain of bitmaps that list the supported PIDs (or OBDMIDs, or InfoTypes) of a service
+///
+/// The bitmap at `0x00` lists `0x01` to `0x20`, and the last bit of each bitmap says whether the
+/// next one, `0x20` higher, is supported. The supported IDs of each ECU are returned, including
+/// the IDs of the bitmaps after the first. If a bitmap after the first is not supported after
+/// all, the IDs found so far are returned.
+pub(crate) fn get_supported_ids<T: Obd2Device>(
+    device: &mut T,
+    service: u8,
+) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+    let mut supported: BTreeMap<EcuAddress, Vec<u8>> = BTreeMap::new();
+    let mut base = 0x00u8;
+    loop {
+        let bitmaps = match device.obd_command_len_by_ecu::<4>(service, base) {
+            Ok(bitmaps) => bitmaps,
+            Err(e) if base != 0 && e.is_unsupported() => {
+                debug!(
+                    "get_supported_ids: service {:02X} bitmap {:02X} not available: {}",
+                    service, base, e
+                );
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        let mut next = false;
+        for (address, bitmap) in bitmaps {
+            let bitmap = u32::from_be_bytes(bitmap);
+            let ids = supported.entry(address).or_default();
+            for i in 1..=0x20u8 {
+                if bitmap & (1 << (0x20 - i)) != 0 {
+                    ids.push(base + i);
+                }
+            }
+            next |= bitmap & 1 != 0;
+        }
+
+        match base.checked_add(0x20) {
+            Some(b) if next => base = b,
+            _ => break,
+        }
+    }
+
+    debug!(
+        "get_supported_ids: service {:02X} supports {:02X?}",
+        service, supported
+    );
+    Ok(supported)
+}
+
+pub(super) fn get_oxygen_sensor_locations<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, BTreeMap<u8, OxygenSensorLocation>>> {
+    // each bit is a sensor, in four banks of two or two banks of four
+    let (present, sensors_per_bank) = match u8::get_obd2_val(device, 0x01, 0x13) {
+        Ok(present) => (present, 4),
+        Err(e) if e.is_unsupported() => (u8::get_obd2_val(device, 0x01, 0x1D)?, 2),
+        Err(e) => return Err(e),
+    };
+
+    Ok(present
+        .into_iter()
+        .map(|(address, bits)| {
+            let sensors = (1..=8)
+                .filter(|n| bits & (1 << (n - 1)) != 0)
+                .map(|n| (n, OxygenSensorLocation::from_number(n, sensors_per_bank)))
+                .collect();
+            (address, sensors)
+        })
+        .collect())
+}
+
+pub(super) fn get_oxygen_sensor_test_results<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, BTreeMap<OxygenSensorLocation, Vec<OxygenSensorTestResult>>>> {
+    let locations = get_oxygen_sensor_locations(device)?;
+
+    // the supported TIDs are listed like PIDs, with sensor number 0
+    let tids: BTreeMap<EcuAddress, Vec<u8>> = match device.obd_request_by_ecu(&[0x05, 0x00, 0x00]) {
+        Ok(bitmaps) => bitmaps
+            .into_iter()
+            .map(|(address, bitmap)| {
+                let bitmap = <[u8; 4]>::try_from(&bitmap[..])
+                    .map_err(|_| Error::IncorrectResponseLength("length", 4, bitmap.len()))?;
+                let bitmap = u32::from_be_bytes(bitmap);
+                let tids = (1..=0x20u8)
+                    .filter(|i| bitmap & (1 << (0x20 - i)) != 0)
+                    .collect();
+                Ok((address, tids))
+            })
+            .collect::<Result<_>>()?,
+        Err(e) if e.is_unsupported() => {
+            debug!(
+                "get_oxygen_sensor_test_results: no supported TIDs, trying standard ones: {}",
+                e
+            );
+            locations
+                .keys()
+                .map(|address| (*address, (0x01..=0x0A).collect()))
+                .collect()
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut requests: Vec<(u8, u8)> = locations
+        .iter()
+        .flat_map(|(address, sensors)| {
+            let tids = tids.get(address).map(Vec::as_slice).unwrap_or_default();
+            sensors
+                .keys()
+                .flat_map(move |sensor| tids.iter().map(move |tid| (*sensor, *tid)))
+        })
+        .collect();
+    requests.sort_unstable();
+    requests.dedup();
+
+    let mut results: BTreeMap<EcuAddress, BTreeMap<OxygenSensorLocation, Vec<_>>> = BTreeMap::new();
+    for (sensor, tid) in requests {
+        let responses = match device.oxygen_sensor_test(tid, sensor) {
+            Ok(responses) => responses,
+            Err(e) if e.is_unsupported() => {
+                debug!(
+                    "get_oxygen_sensor_test_results: TID {:02X} of sensor {} not available: {}",
+                    tid, sensor, e
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        for (address, result) in responses {
+            let Some(location) = locations.get(&address).and_then(|l| l.get(&sensor)) else {
+                debug!(
+                    "get_oxygen_sensor_test_results: {} sent a result for unknown sensor {}",
+                    address, sensor
+                );
+                continue;
+            };
+            results
+                .entry(address)
+                .or_default()
+                .entry(*location)
+                .or_default()
+                .push(result);
+        }
+    }
+
+    Ok(results)
+}
+
+pub(super) fn get_monitor_test_results<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, Vec<MonitorTestResult>>> {
+    let supported = get_supported_data_ids(device, 0x06)?;
+    let mut mids: Vec<u8> = supported.values().flatten().copied().collect();
+    mids.sort_unstable();
+    mids.dedup();
+
+    let mut results: BTreeMap<EcuAddress, Vec<MonitorTestResult>> = BTreeMap::new();
+    for mid in mids {
+        let responses = match device.obd_command_by_ecu(0x06, mid) {
+            Ok(responses) => responses,
+            Err(e) if e.is_unsupported() => {
+                debug!(
+                    "get_monitor_test_results: OBDMID {:02X} not available: {}",
+                    mid, e
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        for (address, data) in responses {
+            // every test repeats the OBDMID, but the first one was stripped with the response
+            // header
+            let mut records = vec![mid];
+            records.extend(data);
+            if !records.len().is_multiple_of(9) {
+                return Err(Error::UnexpectedFrame(
+                    "monitor test results length",
+                    records,
+                ));
+            }
+
+            let entry = results.entry(address).or_default();
+            for record in records.chunks_exact(9) {
+                if record[0] != mid {
+                    return Err(Error::UnexpectedFrame(
+                        "monitor test results OBDMID",
+                        record.to_vec(),
+                    ));
+                }
+                entry.push(MonitorTestResult::from_record(record.try_into().unwrap()));
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+pub(crate) fn get_supported_pids<T: Obd2Device>(
+    device: &mut T,
+) -> Result<BTreeMap<EcuAddress, SupportedPids>> {
+    Ok(get_supported_ids(device, 0x01)?
+        .into_iter()
+        .map(|(address, pids)| (address, pids.into_iter().collect()))
+        .collect())
 }
 
 pub(super) trait GetObd2Values<T>
 where
     Self: Sized,
 {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>>;
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>>;
 }
 
 impl<T: Obd2Device> GetObd2Values<T> for u8 {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
         Ok(device
-            .obd_command_len::<1>(service, pid)?
+            .obd_command_len_by_ecu::<1>(service, pid)?
             .into_iter()
-            .map(|r| r[0])
+            .map(|(a, r)| (a, r[0]))
             .collect())
     }
 }
 
 impl<T: Obd2Device, const N: usize> GetObd2Values<T> for [u8; N] {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
-        device.obd_command_len::<N>(service, pid)
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        device.obd_command_len_by_ecu::<N>(service, pid)
     }
 }
 
 impl<T: Obd2Device> GetObd2Values<T> for u16 {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
         Ok(<[u8; 2]>::get_obd2_val(device, service, pid)?
             .into_iter()
-            .map(Self::from_be_bytes)
+            .map(|(a, r)| (a, Self::from_be_bytes(r)))
             .collect())
     }
 }
 
 impl<T: Obd2Device> GetObd2Values<T> for u32 {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
         Ok(<[u8; 4]>::get_obd2_val(device, service, pid)?
             .into_iter()
-            .map(Self::from_be_bytes)
+            .map(|(a, r)| (a, Self::from_be_bytes(r)))
             .collect())
     }
 }
 
 impl<T: Obd2Device> GetObd2Values<T> for DtcsInfo {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
-        let result = device.obd_command(service, pid)?;
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let result = device.obd_command_by_ecu(service, pid)?;
 
         result
-            .iter()
-            .map(|response| {
+            .into_iter()
+            .map(|(address, response)| {
                 if response.len() == 4 {
-                    Ok(DtcsInfo {
-                        malfunction_indicator_light: (response[0] & 0x80) == 0x80,
-                        dtc_count: response[0] & 0x7f,
-                        common_test_availability: ((response[1] & 0xf0) >> 1)
-                            | (response[1] & 0x07),
-                        is_compression_engine: (response[1] & 0x08) == 0x08,
-                        specific_test_availability: ((response[3] as u16) << 8)
-                            | (response[2] as u16),
-                    })
+                    // the deprecated fields keep the layout they always had
+                    #[allow(deprecated)]
+                    Ok((
+                        address,
+                        DtcsInfo {
+                            malfunction_indicator_light: (response[0] & 0x80) == 0x80,
+                            dtc_count: response[0] & 0x7f,
+                            common_test_availability: ((response[1] & 0xf0) >> 1)
+                                | (response[1] & 0x07),
+                            is_compression_engine: (response[1] & 0x08) == 0x08,
+                            specific_test_availability: (u16::from(response[3]) << 8)
+                                | u16::from(response[2]),
+                            readiness: Readiness::from_bytes([
+                                response[1],
+                                response[2],
+                                response[3],
+                            ]),
+                        },
+                    ))
                 } else {
                     Err(Error::Other(format!(
                         "get_dtc_info: expected length 4, got {}",
@@ -78,67 +473,289 @@ impl<T: Obd2Device> GetObd2Values<T> for DtcsInfo {
 }
 
 impl<T: Obd2Device> GetObd2Values<T> for Dtc {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
         let resp = u16::get_obd2_val(device, service, pid)?;
-        Ok(resp.into_iter().map(|v| v.into()).collect())
+        Ok(resp.into_iter().map(|(a, v)| (a, v.into())).collect())
     }
 }
 
 impl<T: Obd2Device> GetObd2Values<T> for OxygenSensorData {
-    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<Vec<Self>> {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
         let resp = <[u8; 2]>::get_obd2_val(device, service, pid)?;
         Ok(resp
             .into_iter()
-            .map(|v| OxygenSensorData {
-                voltage: f32::from(v[0]) * 0.005,
-                shrft: f32::from(i16::from(v[1]) - 128) / 128.,
+            .map(|(a, v)| {
+                (
+                    a,
+                    OxygenSensorData {
+                        voltage: f32::from(v[0]) * 0.005,
+                        shrft: f32::from(i16::from(v[1]) - 128) / 128.,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for MaximumValues {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 4]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                (
+                    a,
+                    MaximumValues {
+                        equivalence_ratio: v[0],
+                        oxygen_sensor_voltage: v[1],
+                        oxygen_sensor_current: v[2],
+                        intake_manifold_pressure: u16::from(v[3]) * 10,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for Readiness {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 4]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| (a, Readiness::from_bytes([v[1], v[2], v[3]])))
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for EnginePercentTorque {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 5]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                let torque = |v: u8| i16::from(v) - 125;
+                (
+                    a,
+                    EnginePercentTorque {
+                        idle: torque(v[0]),
+                        points: [torque(v[1]), torque(v[2]), torque(v[3]), torque(v[4])],
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for ScrInducementSystem {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 13]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                let mut distances = [0; 6];
+                for (distance, bytes) in distances.iter_mut().zip(v[1..].chunks_exact(2)) {
+                    *distance = u16::from_be_bytes([bytes[0], bytes[1]]);
+                }
+                (
+                    a,
+                    ScrInducementSystem {
+                        state: v[0],
+                        distances,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for DieselExhaustFluidSensor {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 4]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                (
+                    a,
+                    DieselExhaustFluidSensor {
+                        fluid_type: v[0],
+                        concentration: f32::from(v[1]) * 0.25,
+                        temperature: i16::from(v[2]) - 40,
+                        level: f32::from(v[3]) * 100. / 255.,
+                    },
+                )
             })
             .collect())
     }
 }
 
+impl<T: Obd2Device> GetObd2Values<T> for FuelRates {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 4]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                let rate = |high: u8, low: u8| f32::from(u16::from_be_bytes([high, low])) * 0.02;
+                (
+                    a,
+                    FuelRates {
+                        engine: rate(v[0], v[1]),
+                        vehicle: rate(v[2], v[3]),
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for WideRangeOxygenSensorVoltage {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 4]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                (
+                    a,
+                    WideRangeOxygenSensorVoltage {
+                        equivalence_ratio: f32::from(u16::from_be_bytes([v[0], v[1]])) * 2.
+                            / 65536.,
+                        voltage: f32::from(u16::from_be_bytes([v[2], v[3]])) * 8. / 65536.,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for WideRangeOxygenSensorCurrent {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        let resp = <[u8; 4]>::get_obd2_val(device, service, pid)?;
+        Ok(resp
+            .into_iter()
+            .map(|(a, v)| {
+                (
+                    a,
+                    WideRangeOxygenSensorCurrent {
+                        equivalence_ratio: f32::from(u16::from_be_bytes([v[0], v[1]])) * 2.
+                            / 65536.,
+                        current: f32::from(u16::from_be_bytes([v[2], v[3]])) / 256. - 128.,
+                    },
+                )
+            })
+            .collect())
+    }
+}
+
+impl<T: Obd2Device> GetObd2Values<T> for InUsePerformanceTracking {
+    fn get_obd2_val(device: &mut T, service: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        if service != 0x09 {
+            return Err(Error::Other(format!(
+                "in-use performance tracking is not in service {:02X}",
+                service
+            )));
+        }
+
+        get_vehicle_info_items(device, pid, 2)?
+            .into_iter()
+            .map(|(address, counters)| {
+                let counters: Vec<u16> = counters
+                    .into_iter()
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                match Self::from_counters(pid, &counters) {
+                    Some(tracking) => Ok((address, tracking)),
+                    None => Err(Error::Other(format!(
+                        "get_obd2_val: unexpected performance tracking counters {:?}",
+                        counters
+                    ))),
+                }
+            })
+            .collect()
+    }
+}
+
 pub(crate) trait GetObd2ValuesMode<T>
 where
     Self: Sized,
 {
-    fn get_obd2_val_mode(device: &mut T, service: u8) -> Result<Vec<Self>>;
+    fn get_obd2_val_mode(device: &mut T, service: u8) -> Result<BTreeMap<EcuAddress, Self>>;
 }
 
 impl<T: Obd2Device> GetObd2ValuesMode<T> for Vec<Dtc> {
-    fn get_obd2_val_mode(device: &mut T, service: u8) -> Result<Vec<Self>> {
-        let result = device.obd_mode_command(service)?;
-        result
-            .iter()
-            .map(|response| match response.first() {
-                Some(0) => {
-                    if response.len() % 2 == 1 {
-                        let mut ret = Vec::new();
-                        for i in (1..response.len()).step_by(2) {
-                            ret.push(match response[i] >> 6 {
-                                0 => Dtc::Powertrain(0),
-                                1 => Dtc::Chassis(0),
-                                2 => Dtc::Body(0),
-                                3 => Dtc::Network(0),
-                                _ => unreachable!(),
-                            });
-                        }
-                        Ok(ret)
-                    } else {
-                        Err(Error::Other(format!(
-                            "invalid response when getting DTCs {:?}",
-                            response
-                        )))
-                    }
-                }
-                Some(n) if *n <= 3 => todo!(),
-                Some(_) => Err(Error::Other(format!(
-                    "invalid response {:?} when getting DTCs",
-                    response
-                ))),
-                None => Err(Error::Other(
-                    "no response bytes when getting DTCs".to_owned(),
-                )),
-            })
-            .collect::<Result<Vec<Vec<Dtc>>>>()
+    fn get_obd2_val_mode(device: &mut T, service: u8) -> Result<BTreeMap<EcuAddress, Self>> {
+        device
+            .obd_mode_command_by_ecu(service)?
+            .into_iter()
+            .map(|(address, response)| Ok((address, decode_dtcs(response)?)))
+            .collect()
+    }
+}
+
+/// Decode the DTCs in a response to service 03, 07, or 0A
+///
+/// On CAN, the response starts with the number of DTCs. Other protocols send messages of exactly
+/// three DTCs, padded with zeros, and the messages of one ECU are joined.
+fn decode_dtcs(response: Vec<u8>) -> Result<Vec<Dtc>> {
+    let codes = match response.split_first() {
+        Some((&count, codes)) if codes.len() == usize::from(count) * 2 => codes,
+        Some(_) if response.len().is_multiple_of(6) => &response[..],
+        Some(_) => {
+            return Err(Error::UnexpectedFrame(
+                "unknown DTC response format",
+                response,
+            ))
+        }
+        None => return Err(Error::UnexpectedFrame("empty DTC response", response)),
+    };
+
+    Ok(codes
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .filter(|c| *c != 0)
+        .map(Dtc::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_can_dtcs() {
+        // 43 02 04 20 C1 00, without the service byte
+        assert_eq!(
+            decode_dtcs(vec![0x02, 0x04, 0x20, 0xC1, 0x00]).unwrap(),
+            [Dtc::Powertrain(0x0420), Dtc::Network(0x0100)]
+        );
+        assert_eq!(decode_dtcs(vec![0x00]).unwrap(), []);
+    }
+
+    #[test]
+    fn decode_legacy_dtcs() {
+        // two messages of three DTCs, the second padded with zeros
+        assert_eq!(
+            decode_dtcs(vec![
+                0x01, 0x33, 0x04, 0x20, 0xC1, 0x00, 0x92, 0x34, 0x00, 0x00, 0x00, 0x00,
+            ])
+            .unwrap(),
+            [
+                Dtc::Powertrain(0x0133),
+                Dtc::Powertrain(0x0420),
+                Dtc::Network(0x0100),
+                Dtc::Body(0x1234),
+            ]
+        );
+        assert_eq!(decode_dtcs(vec![0; 6]).unwrap(), []);
+    }
+
+    #[test]
+    fn decode_dtcs_unknown_format() {
+        assert!(matches!(
+            decode_dtcs(vec![0x02, 0x04, 0x20]),
+            Err(Error::UnexpectedFrame(_, response)) if response == [0x02, 0x04, 0x20]
+        ));
+        assert!(matches!(
+            decode_dtcs(vec![]),
+            Err(Error::UnexpectedFrame(_, _))
+        ));
     }
 }
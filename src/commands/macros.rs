@@ -1,68 +1,33 @@
-macro_rules! trait_func {
-    {
-        $(#[$attr:meta])*
-        fn $name:ident($service:expr) -> $retrieve_type:ty;
-    } => {
-        $(#[$attr])*
-        fn $name(&mut self) -> Result<Vec<$retrieve_type>>;
-    };
-    {
-        $(#[$attr:meta])*
-        fn $name:ident($service:expr, $pid:expr) -> $retrieve_type:ty;
-    } => {
-        $(#[$attr])*
-        fn $name(&mut self) -> Result<Vec<$retrieve_type>>;
-    };
-    {
-        $(#[$attr:meta])*
-        fn $name:ident($service:expr, $pid:expr, $map:expr) -> $retrieve_type:ty;
-    } => {
-        $(#[$attr])*
-        fn $name(&mut self) -> Result<Vec<$retrieve_type>>;
-    };
-    {
-        $(#[$attr:meta])*
-        fn $name:ident<$retrieve_type:ty>($service:expr, $pid:expr, $map:expr) -> $out_type:ty;
-    } => {
-        $(#[$attr])*
-        fn $name(&mut self) -> Result<Vec<$out_type>>;
-    };
-}
-
 macro_rules! impl_func {
     {
-        $(#[$attr:meta])*
         fn $name:ident($service:expr) -> $retrieve_type:ty;
     } => {
-        fn $name(&mut self) -> Result<Vec<$retrieve_type>> {
+        fn $name(&mut self) -> Result<BTreeMap<EcuAddress, $retrieve_type>> {
             <$retrieve_type>::get_obd2_val_mode(self, $service)
         }
     };
     {
-        $(#[$attr:meta])*
         fn $name:ident($service:expr, $pid:expr) -> $retrieve_type:ty;
     } => {
-        fn $name(&mut self) -> Result<Vec<$retrieve_type>> {
+        fn $name(&mut self) -> Result<BTreeMap<EcuAddress, $retrieve_type>> {
             <$retrieve_type>::get_obd2_val(self, $service, $pid)
         }
     };
     {
-        $(#[$attr:meta])*
         fn $name:ident($service:expr, $pid:expr, $map:expr) -> $retrieve_type:ty;
     } => {
-        fn $name(&mut self) -> Result<Vec<$retrieve_type>> {
+        fn $name(&mut self) -> Result<BTreeMap<EcuAddress, $retrieve_type>> {
             $map(<$retrieve_type>::get_obd2_val(self, $service, $pid))
         }
     };
     {
-        $(#[$attr:meta])*
         fn $name:ident<$retrieve_type:ty>($service:expr, $pid:expr, $map:expr) -> $out_type:ty;
     } => {
-        fn $name(&mut self) -> Result<Vec<$out_type>> {
+        fn $name(&mut self) -> Result<BTreeMap<EcuAddress, $out_type>> {
             Ok(
                 <$retrieve_type>::get_obd2_val(self, $service, $pid)?
                     .into_iter()
-                    .map(|v| $map(v.into()))
+                    .map(|(address, v)| (address, $map(v.into())))
                     .collect()
             )
         }
@@ -95,15 +60,21 @@ macro_rules! func {
             )+)?
 
             $(
-                trait_func! {
-                    $(#[$attr_inner])*
-                    ///
+                $(#[$attr_inner])*
+                ///
+                #[doc=concat!(
+                    "Details: service ", $service,
+                    $(", PID ", $pid,)?
+                    ", read type: `", decode_type!($output $(, $retrieve_type)?), "`"
+                )]
+                fn $name(&mut self) -> Result<Vec<$output>>;
+
+                paste::paste! {
                     #[doc=concat!(
-                        "Details: service ", $service,
-                        $(", PID ", $pid,)?
-                        ", read type: `", decode_type!($output $(, $retrieve_type)?), "`"
+                        "Like [", stringify!($name), "](Self::", stringify!($name), "), but with ",
+                        "the value from each ECU keyed by its address"
                     )]
-                    fn $name$(<$retrieve_type>)?($service$(, $pid$(, $map)?)?) -> $output;
+                    fn [<$name _by_ecu>](&mut self) -> Result<BTreeMap<EcuAddress, $output>>;
                 }
             )*
         }
@@ -115,9 +86,16 @@ macro_rules! func {
             )+)?
 
             $(
-                impl_func! {
-                    $(#[$attr_inner])*
-                    fn $name$(<$retrieve_type>)?($service$(, $pid$(, $map)?)?) -> $output;
+                fn $name(&mut self) -> Result<Vec<$output>> {
+                    paste::paste! {
+                        Ok(self.[<$name _by_ecu>]()?.into_values().collect())
+                    }
+                }
+
+                paste::paste! {
+                    impl_func! {
+                        fn [<$name _by_ecu>]$(<$retrieve_type>)?($service$(, $pid$(, $map)?)?) -> $output;
+                    }
                 }
             )*
         }
@@ -132,3 +110,50 @@ macro_rules! decode_type {
         stringify!($t)
     };
 }
+
+/// Define a struct for a PID whose first byte says which of the other fields are supported
+///
+/// Each field lists its bit in the support byte, the type and offset of its raw value in the
+/// response, and how the raw value is converted. Fields that are not supported are `None`.
+macro_rules! record {
+    {
+        $(#[$attr:meta])*
+        pub struct $name:ident[$len:literal] {
+            $(
+                $(#[$f_attr:meta])*
+                $field:ident: $output:ty = bit $bit:literal, <$raw:ty>($offset:literal, $map:expr),
+            )+
+        }
+    } => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #[non_exhaustive]
+        pub struct $name {
+            $(
+                $(#[$f_attr])*
+                pub $field: Option<$output>,
+            )+
+        }
+
+        impl<T: Obd2Device> GetObd2Values<T> for $name {
+            fn get_obd2_val(
+                device: &mut T,
+                service: u8,
+                pid: u8,
+            ) -> Result<BTreeMap<EcuAddress, Self>> {
+                Ok(<[u8; $len]>::get_obd2_val(device, service, pid)?
+                    .into_iter()
+                    .map(|(address, v)| {
+                        let record = $name {
+                            $(
+                                $field: (v[0] & (1 << $bit) != 0)
+                                    .then(|| $map(<$raw as Field>::read(&v[$offset..]).into())),
+                            )+
+                        };
+                        (address, record)
+                    })
+                    .collect())
+            }
+        }
+    };
+}
@@ -5,16 +5,43 @@
 //! Wikipedia](https://en.wikipedia.org/wiki/OBD-II_PIDs). This module mostly uses service 1.
 
 mod implementation;
+pub(crate) use implementation::{get_supported_ids, get_supported_pids};
 use implementation::{GetObd2Values, GetObd2ValuesMode};
 
 #[macro_use]
 mod macros;
 
+mod monitoring;
+
+mod records;
+pub use records::{
+    AbsDisableSwitch, AecdRunTime, AecdTimers, AuxiliaryInputOutput, BoostPressureControl,
+    ChargeAirCoolerTemperatures, CoolantTemperatureSensors, DieselAftertreatment,
+    DieselExhaustFluidDosing, EcuObdSystemInformation, EgrControl, EgrTemperatureSensors,
+    EngineRunTime, EvapSystemVaporPressure, ExhaustGasTemperatures, ExhaustPressure,
+    FuelPressureControl, FuelSystemControl, FuelSystemUse, HybridBatterySystem,
+    InjectionPressureControl, IntakeAirFlowControl, IntakeAirTemperatureSensors,
+    IntakeManifoldPressureSensors, MassAirFlowSensors, NoxReagentSystem, NoxSensors,
+    NoxSensors3And4, NoxWarningSystem, OxygenSensorConcentrations, ParticulateFilterPressure,
+    ParticulateFilterTemperatures, ParticulateMatterSensorOutput, ParticulateMatterSensors,
+    ThrottleActuatorControl, TransmissionGear, TurbochargerInletPressure, TurbochargerSpeed,
+    TurbochargerTemperatures, VehicleObdCounters, VehicleObdSystemInformation, VgtControl,
+    WastegateControl, WideRangeOxygenSensors,
+};
+
 mod types;
 use types::private;
-pub use types::{Dtc, DtcsInfo, OxygenSensorData};
+pub use types::{
+    DieselExhaustFluidSensor, Dtc, DtcReport, DtcStatus, DtcsInfo, EnginePercentTorque, FuelRates,
+    FuelType, InUsePerformanceTracking, MaximumValues, MonitorRatio, MonitorReadiness,
+    MonitorTestResult, NteStatus, OxygenSensorData, OxygenSensorLocation, OxygenSensorTestResult,
+    ParseDtcError, PerformanceMonitor, Readiness, ReadinessMonitor, ScrInducementSystem,
+    SupportedPids, WideRangeOxygenSensorCurrent, WideRangeOxygenSensorVoltage,
+};
 
-use crate::{Obd2Device, Result};
+use std::collections::BTreeMap;
+
+use crate::{EcuAddress, Obd2Device, Result};
 
 func! {
     /// Trait for devices that can retrieve data over OBD-II
@@ -28,14 +55,195 @@ func! {
         ///
         /// This should match the number printed on the vehicle, and is a good command for checking
         /// that the OBD-II interface is working correctly.
+        ///
+        /// # Example
+        /// ```
+        /// use obd2::{commands::Obd2DataRetrieval, device::{HeaderFormat, Mock}, Obd2};
+        ///
+        /// // protocols other than CAN split the VIN into numbered messages
+        /// let mut mock = Mock::new();
+        /// mock.headers(HeaderFormat::Legacy).expect(
+        ///     &[0x09, 0x02],
+        ///     "48 6B 10 49 02 01 00 00 00 31 00\n48 6B 10 49 02 02 44 34 47 50 00\n\
+        ///      48 6B 10 49 02 03 30 30 52 35 00\n48 6B 10 49 02 04 35 42 31 32 00\n\
+        ///      48 6B 10 49 02 05 33 34 35 36 00",
+        /// );
+        ///
+        /// let mut device = Obd2::new(mock);
+        /// assert_eq!(device.get_vin().unwrap(), "1D4GP00R55B123456");
+        /// ```
         fn get_vin(self, 0x09, 0x02) -> Result<String> {
             implementation::get_vin(self)
         }
+
+        /// Get the service 09 InfoTypes supported by each ECU
+        ///
+        /// This reads the bitmaps at InfoType `0x00`, `0x20`, and so on, and leaves out the IDs of
+        /// the bitmaps themselves.
+        fn get_supported_info_types(self, 0x09, 0x00) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            implementation::get_supported_data_ids(self, 0x09)
+        }
+
+        /// Get the calibration IDs of the software of each ECU
+        ///
+        /// An ECU can have several calibration IDs, such as one for each software module. Each is
+        /// up to 16 characters.
+        fn get_calibration_ids(self, 0x09, 0x04) -> Result<BTreeMap<EcuAddress, Vec<String>>> {
+            implementation::get_calibration_ids(self)
+        }
+
+        /// Get the calibration verification numbers of each ECU
+        ///
+        /// These are checksums of the software, one for each [calibration
+        /// ID](Self::get_calibration_ids) in the same order, and show whether the software was
+        /// modified.
+        fn get_calibration_verification_numbers(self, 0x09, 0x06)
+            -> Result<BTreeMap<EcuAddress, Vec<u32>>>
+        {
+            implementation::get_calibration_verification_numbers(self)
+        }
+
+        /// Get the name of each ECU
+        ///
+        /// The name starts with an abbreviation of up to four characters, followed by a `-` and the
+        /// full name, like `ECM-EngineControl`.
+        fn get_ecu_name(self, 0x09, 0x0A) -> Result<BTreeMap<EcuAddress, String>> {
+            implementation::get_vehicle_info_string(self, 0x0A)
+        }
+
+        /// Get the engine serial number (ESN), from the ECUs of heavy duty vehicles
+        fn get_engine_serial_number(self, 0x09, 0x0D) -> Result<BTreeMap<EcuAddress, String>> {
+            implementation::get_vehicle_info_string(self, 0x0D)
+        }
+
+        /// Get the exhaust regulation or type approval number, from the ECUs of heavy duty
+        /// vehicles
+        fn get_exhaust_regulation_number(self, 0x09, 0x0F)
+            -> Result<BTreeMap<EcuAddress, String>>
+        {
+            implementation::get_vehicle_info_string(self, 0x0F)
+        }
+
+        /// Get the confirmed, pending, and permanent DTCs of each ECU
+        ///
+        /// This combines [get_dtcs](Self::get_dtcs), [get_pending_dtcs](Self::get_pending_dtcs),
+        /// and [get_permanent_dtcs](Self::get_permanent_dtcs). If no ECU supports one of the
+        /// services, like permanent DTCs on vehicles from before 2010, its list is left empty.
+        fn get_dtc_report(self, 0x03) -> Result<BTreeMap<EcuAddress, DtcReport>> {
+            implementation::get_dtc_report(self)
+        }
+
+        /// Get the service 01 PIDs supported by each ECU
+        ///
+        /// This follows the whole chain of bitmaps, from PID `0x00` up to `0xE0`.
+        /// [Obd2::supported_pids](crate::Obd2::supported_pids) returns the same, but only reads
+        /// the bitmaps once.
+        fn get_supported_pids(self, 0x01, 0x00) -> Result<BTreeMap<EcuAddress, SupportedPids>> {
+            implementation::get_supported_pids(self)
+        }
+
+        /// Get the location of each oxygen sensor of each ECU, keyed by the number of the sensor
+        ///
+        /// The number is the one used by the oxygen sensor PIDs, like
+        /// [get_oxygen_sensor_1](Self::get_oxygen_sensor_1), and by service 05. This reads
+        /// [get_oxygen_sensors_2_bank](Self::get_oxygen_sensors_2_bank), or
+        /// [get_oxygen_sensors_4_bank](Self::get_oxygen_sensors_4_bank) if the ECUs do not
+        /// support it.
+        fn get_oxygen_sensor_locations(self, 0x01, 0x13)
+            -> Result<BTreeMap<EcuAddress, BTreeMap<u8, OxygenSensorLocation>>>
+        {
+            implementation::get_oxygen_sensor_locations(self)
+        }
+
+        /// Get the results of the oxygen sensor monitoring tests of each ECU, grouped by sensor
+        ///
+        /// Every supported test is requested for every oxygen sensor, with
+        /// [Obd2Device::oxygen_sensor_test]. This is for vehicles that do not use CAN; on CAN, use
+        /// [get_monitor_test_results](Self::get_monitor_test_results) instead.
+        fn get_oxygen_sensor_test_results(self, 0x05)
+            -> Result<
+                BTreeMap<EcuAddress, BTreeMap<OxygenSensorLocation, Vec<OxygenSensorTestResult>>>,
+            >
+        {
+            implementation::get_oxygen_sensor_test_results(self)
+        }
+
+        /// Get the OBDMIDs (on-board monitor IDs) supported by each ECU
+        ///
+        /// This reads the bitmaps at OBDMID `0x00`, `0x20`, and so on, and leaves out the IDs of
+        /// the bitmaps themselves. Service 06 is only defined like this on CAN.
+        fn get_supported_monitor_ids(self, 0x06, 0x00) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            implementation::get_supported_data_ids(self, 0x06)
+        }
+
+        /// Get the service 08 TIDs supported by each ECU
+        ///
+        /// These are the tests that can be run with [Obd2Device::on_board_control]. Only the
+        /// bitmaps at TID `0x00`, `0x20`, and so on are read, so no test is started.
+        fn get_supported_control_tests(self, 0x08, 0x00) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            implementation::get_supported_data_ids(self, 0x08)
+        }
+
+        /// Get the results of the latest on-board monitor tests of each ECU
+        ///
+        /// Every supported OBDMID is requested, and each of its tests is decoded. This is where
+        /// the details of the emissions monitors are, like catalyst efficiency, misfire counts for
+        /// each cylinder, or EVAP leak tests. Monitors that have not completed report zero for the
+        /// value and limits. Service 06 is only defined like this on CAN.
+        fn get_monitor_test_results(self, 0x06)
+            -> Result<BTreeMap<EcuAddress, Vec<MonitorTestResult>>>
+        {
+            implementation::get_monitor_test_results(self)
+        }
+
+        /// Get the data of PID `0xC1` from each ECU, without decoding it
+        ///
+        /// The layout of PIDs `0xC1` to `0xC4` is not publicly defined, and varies between
+        /// manufacturers, so the data is returned as it was sent.
+        fn get_pid_c1_data(self, 0x01, 0xC1) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            self.obd_command_by_ecu(0x01, 0xC1)
+        }
+
+        /// Like [get_pid_c1_data](Self::get_pid_c1_data) but for PID `0xC2`
+        fn get_pid_c2_data(self, 0x01, 0xC2) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            self.obd_command_by_ecu(0x01, 0xC2)
+        }
+
+        /// Like [get_pid_c1_data](Self::get_pid_c1_data) but for PID `0xC3`
+        fn get_pid_c3_data(self, 0x01, 0xC3) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            self.obd_command_by_ecu(0x01, 0xC3)
+        }
+
+        /// Like [get_pid_c1_data](Self::get_pid_c1_data) but for PID `0xC4`
+        fn get_pid_c4_data(self, 0x01, 0xC4) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+            self.obd_command_by_ecu(0x01, 0xC4)
+        }
     }
 
     /// Get list of DTCs for each ECU
+    ///
+    /// These are the confirmed DTCs, which may turn on the malfunction indicator light.
     fn get_dtcs(0x03) -> Vec<Dtc>;
 
+    /// Get list of pending DTCs for each ECU
+    ///
+    /// Pending DTCs were detected during the current or last completed drive cycle, but have not
+    /// been detected often enough to be confirmed. They do not turn on the malfunction indicator
+    /// light.
+    fn get_pending_dtcs(0x07) -> Vec<Dtc>;
+
+    /// Get list of permanent DTCs for each ECU
+    ///
+    /// Permanent DTCs cannot be cleared with service 04; the ECU erases them itself once the
+    /// monitor that set them passes.
+    fn get_permanent_dtcs(0x0A) -> Vec<Dtc>;
+
+    /// Get in-use monitor performance tracking for spark ignition engines
+    fn get_spark_performance_tracking(0x09, 0x08) -> InUsePerformanceTracking;
+
+    /// Get in-use monitor performance tracking for compression ignition (Diesel) engines
+    fn get_compression_performance_tracking(0x09, 0x0B) -> InUsePerformanceTracking;
+
     /// Get service 1 PID support for $01 to $20
     fn get_service_1_pid_support_1(0x01, 0x00) -> u32;
 
@@ -221,6 +429,616 @@ func! {
     /// Get service 1 PID support for $21 to $40
     fn get_service_1_pid_support_2(0x01, 0x20) -> u32;
 
+    /// Get the distance traveled with the malfunction indicator light on in km
+    fn get_distance_with_mil_on(0x01, 0x21) -> u16;
+
+    /// Get the fuel rail pressure in kPa, relative to the intake manifold vacuum
+    fn get_fuel_rail_pressure<u16>(0x01, 0x22, |v: f32| v * 0.079) -> f32;
+
+    /// Get the fuel rail gauge pressure in kPa
+    ///
+    /// This is for Diesel engines and gasoline engines with direct injection, which have a much
+    /// higher fuel pressure. It is measured relative to the atmosphere.
+    fn get_fuel_rail_gauge_pressure<u16>(0x01, 0x23, |v: f32| v * 10.) -> f32;
+
+    /// Get wide range oxygen sensor 1 equivalence ratio and voltage
+    ///
+    /// This is for wide range (or "air/fuel ratio") sensors, which measure the air/fuel ratio
+    /// instead of only whether the mix is rich or lean. The sensor numbers are the same as for
+    /// [get_oxygen_sensor_1](Self::get_oxygen_sensor_1) and the following PIDs.
+    fn get_wide_range_oxygen_sensor_1_voltage(0x01, 0x24) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 2
+    fn get_wide_range_oxygen_sensor_2_voltage(0x01, 0x25) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 3
+    fn get_wide_range_oxygen_sensor_3_voltage(0x01, 0x26) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 4
+    fn get_wide_range_oxygen_sensor_4_voltage(0x01, 0x27) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 5
+    fn get_wide_range_oxygen_sensor_5_voltage(0x01, 0x28) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 6
+    fn get_wide_range_oxygen_sensor_6_voltage(0x01, 0x29) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 7
+    fn get_wide_range_oxygen_sensor_7_voltage(0x01, 0x2A) -> WideRangeOxygenSensorVoltage;
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage)
+    /// but for sensor 8
+    fn get_wide_range_oxygen_sensor_8_voltage(0x01, 0x2B) -> WideRangeOxygenSensorVoltage;
+
+    /// Get the commanded EGR (exhaust gas recirculation) in %
+    fn get_commanded_egr<u8>(0x01, 0x2C, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the EGR error in %
+    ///
+    /// This is the difference between the actual and the [commanded
+    /// EGR](Self::get_commanded_egr), relative to the commanded EGR. Negative values mean less
+    /// exhaust gas is recirculated than commanded.
+    fn get_egr_error<u8>(0x01, 0x2D, |v: f32| (v - 128.) * 100. / 128.) -> f32;
+
+    /// Get the commanded evaporative purge in %
+    ///
+    /// This is how far the valve that lets fuel vapour from the EVAP system into the intake is
+    /// commanded to open.
+    fn get_commanded_evaporative_purge<u8>(0x01, 0x2E, |v: f32| v * 100. / 255.) -> f32;
+
     // Get the fuel level (out of 255)
     fn get_fuel_level(0x01, 0x2F) -> u8;
+
+    /// Get the number of warm-ups since the DTCs were cleared
+    ///
+    /// A warm-up is when the coolant temperature rises by at least 22 ºC after the engine is
+    /// started, and reaches at least 70 ºC (60 ºC for Diesel engines).
+    fn get_warm_ups_since_codes_cleared(0x01, 0x30) -> u8;
+
+    /// Get the distance traveled since the DTCs were cleared in km
+    fn get_distance_since_codes_cleared(0x01, 0x31) -> u16;
+
+    /// Get the EVAP system vapour pressure in Pa
+    ///
+    /// This is measured relative to the atmosphere, in the fuel tank or the vapour lines.
+    fn get_evap_system_vapor_pressure<[u8; 2]>(
+        0x01,
+        0x32,
+        |v: [u8; 2]| f32::from(i16::from_be_bytes(v)) / 4.
+    ) -> f32;
+
+    /// Get the absolute barometric pressure in kPa
+    fn get_barometric_pressure(0x01, 0x33) -> u8;
+
+    /// Get wide range oxygen sensor 1 equivalence ratio and current
+    ///
+    /// Like [get_wide_range_oxygen_sensor_1_voltage](Self::get_wide_range_oxygen_sensor_1_voltage),
+    /// but for sensors that report the current through the sensor instead of its voltage.
+    fn get_wide_range_oxygen_sensor_1_current(0x01, 0x34) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 2
+    fn get_wide_range_oxygen_sensor_2_current(0x01, 0x35) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 3
+    fn get_wide_range_oxygen_sensor_3_current(0x01, 0x36) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 4
+    fn get_wide_range_oxygen_sensor_4_current(0x01, 0x37) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 5
+    fn get_wide_range_oxygen_sensor_5_current(0x01, 0x38) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 6
+    fn get_wide_range_oxygen_sensor_6_current(0x01, 0x39) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 7
+    fn get_wide_range_oxygen_sensor_7_current(0x01, 0x3A) -> WideRangeOxygenSensorCurrent;
+    /// Like [get_wide_range_oxygen_sensor_1_current](Self::get_wide_range_oxygen_sensor_1_current)
+    /// but for sensor 8
+    fn get_wide_range_oxygen_sensor_8_current(0x01, 0x3B) -> WideRangeOxygenSensorCurrent;
+
+    /// Get the temperature of the catalyst in bank 1, sensor 1 in ºC
+    fn get_catalyst_temperature_bank_1_sensor_1<u16>(0x01, 0x3C, |v: f32| v / 10. - 40.) -> f32;
+    /// Like [get_catalyst_temperature_bank_1_sensor_1](Self::get_catalyst_temperature_bank_1_sensor_1)
+    /// but for bank 2, sensor 1
+    fn get_catalyst_temperature_bank_2_sensor_1<u16>(0x01, 0x3D, |v: f32| v / 10. - 40.) -> f32;
+    /// Like [get_catalyst_temperature_bank_1_sensor_1](Self::get_catalyst_temperature_bank_1_sensor_1)
+    /// but for bank 1, sensor 2
+    fn get_catalyst_temperature_bank_1_sensor_2<u16>(0x01, 0x3E, |v: f32| v / 10. - 40.) -> f32;
+    /// Like [get_catalyst_temperature_bank_1_sensor_1](Self::get_catalyst_temperature_bank_1_sensor_1)
+    /// but for bank 2, sensor 2
+    fn get_catalyst_temperature_bank_2_sensor_2<u16>(0x01, 0x3F, |v: f32| v / 10. - 40.) -> f32;
+
+    /// Get service 1 PID support for $41 to $60
+    fn get_service_1_pid_support_3(0x01, 0x40) -> u32;
+
+    /// Get the status of the readiness monitors in this drive cycle
+    ///
+    /// Unlike the [readiness](DtcsInfo::readiness) in [get_dtc_info](Self::get_dtc_info), this
+    /// shows which monitors are enabled and complete since the engine was started.
+    fn get_monitor_status_this_drive_cycle(0x01, 0x41) -> Readiness;
+
+    /// Get the control module voltage in V
+    ///
+    /// This is the supply voltage of the ECU, which is usually close to the battery voltage.
+    fn get_control_module_voltage<u16>(0x01, 0x42, |v: f32| v / 1000.) -> f32;
+
+    /// Get the absolute load value in %
+    ///
+    /// This is the air mass per intake stroke relative to the maximum at wide open throttle and
+    /// sea level, so it can be more than 100% for engines with a turbocharger or supercharger.
+    fn get_absolute_load<u16>(0x01, 0x43, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the commanded air/fuel equivalence ratio (λ)
+    ///
+    /// Values above 1 are lean. It ranges from 0 to just under 2.
+    fn get_commanded_equivalence_ratio<u16>(0x01, 0x44, |v: f32| v * 2. / 65536.) -> f32;
+
+    /// Get the relative throttle position in %
+    ///
+    /// This is relative to the learned closed throttle position, unlike
+    /// [get_throttle_position](Self::get_throttle_position).
+    fn get_relative_throttle_position<u8>(0x01, 0x45, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the ambient air temperature in ºC
+    fn get_ambient_air_temperature<u8>(0x01, 0x46, |v: i16| v - 40) -> i16;
+
+    /// Get absolute throttle position B in %
+    fn get_absolute_throttle_position_b<u8>(0x01, 0x47, |v: f32| v * 100. / 255.) -> f32;
+    /// Get absolute throttle position C in %
+    fn get_absolute_throttle_position_c<u8>(0x01, 0x48, |v: f32| v * 100. / 255.) -> f32;
+    /// Get accelerator pedal position D in %
+    fn get_accelerator_pedal_position_d<u8>(0x01, 0x49, |v: f32| v * 100. / 255.) -> f32;
+    /// Get accelerator pedal position E in %
+    fn get_accelerator_pedal_position_e<u8>(0x01, 0x4A, |v: f32| v * 100. / 255.) -> f32;
+    /// Get accelerator pedal position F in %
+    fn get_accelerator_pedal_position_f<u8>(0x01, 0x4B, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the commanded throttle actuator position in %
+    fn get_commanded_throttle_actuator<u8>(0x01, 0x4C, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the time run with the malfunction indicator light on in minutes
+    fn get_time_with_mil_on(0x01, 0x4D) -> u16;
+
+    /// Get the time since the DTCs were cleared in minutes
+    fn get_time_since_codes_cleared(0x01, 0x4E) -> u16;
+
+    /// Get the maximum values of the equivalence ratio, oxygen sensors, and intake manifold
+    /// pressure
+    ///
+    /// These are the largest values that the ECU can report for the related PIDs.
+    fn get_maximum_values(0x01, 0x4F) -> MaximumValues;
+
+    /// Get the maximum air flow rate that the mass air flow sensor can report in g/s
+    fn get_maximum_air_flow_rate<[u8; 4]>(0x01, 0x50, |v: [u8; 4]| u16::from(v[0]) * 10) -> u16;
+
+    /// Get the type of fuel the vehicle uses
+    fn get_fuel_type<u8>(0x01, 0x51, |v: u8| FuelType::from(v)) -> FuelType;
+
+    /// Get the ethanol content of the fuel in %
+    fn get_ethanol_fuel_percentage<u8>(0x01, 0x52, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the absolute EVAP system vapour pressure in kPa
+    fn get_absolute_evap_system_vapor_pressure<u16>(0x01, 0x53, |v: f32| v / 200.) -> f32;
+
+    /// Get the EVAP system vapour pressure in Pa
+    ///
+    /// Like [get_evap_system_vapor_pressure](Self::get_evap_system_vapor_pressure), but with a
+    /// wider range.
+    fn get_evap_system_vapor_pressure_wide<[u8; 2]>(
+        0x01,
+        0x54,
+        |v: [u8; 2]| i16::from_be_bytes(v)
+    ) -> i16;
+
+    /// Get the short term secondary oxygen sensor trims for banks 1 and 3
+    ///
+    /// Like [get_short_term_fuel_trim_1](Self::get_short_term_fuel_trim_1), this ranges from
+    /// about -1 to 1. The second value is for bank 3.
+    fn get_short_term_secondary_oxygen_trim_1_3<[u8; 2]>(
+        0x01,
+        0x55,
+        |v: [u8; 2]| v.map(|v| (f32::from(v) / 128.) - 1.)
+    ) -> [f32; 2];
+    /// Like [get_short_term_secondary_oxygen_trim_1_3](Self::get_short_term_secondary_oxygen_trim_1_3)
+    /// but long term
+    fn get_long_term_secondary_oxygen_trim_1_3<[u8; 2]>(
+        0x01,
+        0x56,
+        |v: [u8; 2]| v.map(|v| (f32::from(v) / 128.) - 1.)
+    ) -> [f32; 2];
+    /// Like [get_short_term_secondary_oxygen_trim_1_3](Self::get_short_term_secondary_oxygen_trim_1_3)
+    /// but for banks 2 and 4
+    fn get_short_term_secondary_oxygen_trim_2_4<[u8; 2]>(
+        0x01,
+        0x57,
+        |v: [u8; 2]| v.map(|v| (f32::from(v) / 128.) - 1.)
+    ) -> [f32; 2];
+    /// Like [get_long_term_secondary_oxygen_trim_1_3](Self::get_long_term_secondary_oxygen_trim_1_3)
+    /// but for banks 2 and 4
+    fn get_long_term_secondary_oxygen_trim_2_4<[u8; 2]>(
+        0x01,
+        0x58,
+        |v: [u8; 2]| v.map(|v| (f32::from(v) / 128.) - 1.)
+    ) -> [f32; 2];
+
+    /// Get the fuel rail absolute pressure in kPa
+    fn get_fuel_rail_absolute_pressure<u16>(0x01, 0x59, |v: f32| v * 10.) -> f32;
+
+    /// Get the relative accelerator pedal position in %
+    fn get_relative_accelerator_pedal_position<u8>(0x01, 0x5A, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the remaining life of the hybrid battery pack in %
+    fn get_hybrid_battery_remaining_life<u8>(0x01, 0x5B, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the engine oil temperature in ºC
+    fn get_engine_oil_temperature<u8>(0x01, 0x5C, |v: i16| v - 40) -> i16;
+
+    /// Get the fuel injection timing in degrees
+    ///
+    /// Positive values are before top dead center.
+    fn get_fuel_injection_timing<u16>(0x01, 0x5D, |v: f32| v / 128. - 210.) -> f32;
+
+    /// Get the engine fuel rate in L/h
+    fn get_engine_fuel_rate<u16>(0x01, 0x5E, |v: f32| v / 20.) -> f32;
+
+    /// Get the emission requirements the vehicle is designed for (bitfield)
+    fn get_emission_requirements(0x01, 0x5F) -> u8;
+
+    /// Get service 1 PID support for $61 to $80
+    fn get_service_1_pid_support_4(0x01, 0x60) -> u32;
+
+    /// Get the torque requested by the driver in %, relative to the [engine reference
+    /// torque](Self::get_engine_reference_torque)
+    fn get_driver_demand_torque<u8>(0x01, 0x61, |v: i16| v - 125) -> i16;
+
+    /// Get the actual engine torque in %, relative to the [engine reference
+    /// torque](Self::get_engine_reference_torque)
+    fn get_actual_torque<u8>(0x01, 0x62, |v: i16| v - 125) -> i16;
+
+    /// Get the engine reference torque in Nm
+    fn get_engine_reference_torque(0x01, 0x63) -> u16;
+
+    /// Get the engine torque at idle and at four points of the torque curve
+    fn get_engine_percent_torque(0x01, 0x64) -> EnginePercentTorque;
+
+    /// Get the status of auxiliary inputs and outputs, like power take-off
+    fn get_auxiliary_input_output(0x01, 0x65) -> AuxiliaryInputOutput;
+
+    /// Get the air flow rates of the mass air flow sensors
+    fn get_mass_air_flow_sensors(0x01, 0x66) -> MassAirFlowSensors;
+
+    /// Get the engine coolant temperatures
+    fn get_coolant_temperature_sensors(0x01, 0x67) -> CoolantTemperatureSensors;
+
+    /// Get the intake air temperatures
+    fn get_intake_air_temperature_sensors(0x01, 0x68) -> IntakeAirTemperatureSensors;
+
+    /// Get the commanded and actual EGR (exhaust gas recirculation), and the EGR errors
+    fn get_egr_control(0x01, 0x69) -> EgrControl;
+
+    /// Get the commanded and actual Diesel intake air flow control
+    fn get_intake_air_flow_control(0x01, 0x6A) -> IntakeAirFlowControl;
+
+    /// Get the EGR (exhaust gas recirculation) temperatures
+    fn get_egr_temperature_sensors(0x01, 0x6B) -> EgrTemperatureSensors;
+
+    /// Get the commanded and actual throttle positions
+    fn get_throttle_actuator_control(0x01, 0x6C) -> ThrottleActuatorControl;
+
+    /// Get the commanded and actual fuel rail pressures, and the fuel temperatures
+    fn get_fuel_pressure_control(0x01, 0x6D) -> FuelPressureControl;
+
+    /// Get the commanded and actual injection control pressures
+    fn get_injection_pressure_control(0x01, 0x6E) -> InjectionPressureControl;
+
+    /// Get the turbocharger compressor inlet pressures
+    fn get_turbocharger_inlet_pressure(0x01, 0x6F) -> TurbochargerInletPressure;
+
+    /// Get the commanded and actual boost pressures
+    fn get_boost_pressure_control(0x01, 0x70) -> BoostPressureControl;
+
+    /// Get the commanded and actual VGT (variable geometry turbocharger) positions
+    fn get_vgt_control(0x01, 0x71) -> VgtControl;
+
+    /// Get the commanded and actual wastegate positions
+    fn get_wastegate_control(0x01, 0x72) -> WastegateControl;
+
+    /// Get the exhaust pressures
+    fn get_exhaust_pressure(0x01, 0x73) -> ExhaustPressure;
+
+    /// Get the turbocharger speeds
+    fn get_turbocharger_speed(0x01, 0x74) -> TurbochargerSpeed;
+
+    /// Get the temperatures of turbocharger A
+    fn get_turbocharger_a_temperatures(0x01, 0x75) -> TurbochargerTemperatures;
+    /// Like [get_turbocharger_a_temperatures](Self::get_turbocharger_a_temperatures) but for
+    /// turbocharger B
+    fn get_turbocharger_b_temperatures(0x01, 0x76) -> TurbochargerTemperatures;
+
+    /// Get the charge air cooler temperatures
+    fn get_charge_air_cooler_temperatures(0x01, 0x77) -> ChargeAirCoolerTemperatures;
+
+    /// Get the exhaust gas temperatures of bank 1
+    fn get_exhaust_gas_temperatures_bank_1(0x01, 0x78) -> ExhaustGasTemperatures;
+    /// Like [get_exhaust_gas_temperatures_bank_1](Self::get_exhaust_gas_temperatures_bank_1) but
+    /// for bank 2
+    fn get_exhaust_gas_temperatures_bank_2(0x01, 0x79) -> ExhaustGasTemperatures;
+
+    /// Get the Diesel particulate filter pressures of bank 1
+    fn get_particulate_filter_pressure_bank_1(0x01, 0x7A) -> ParticulateFilterPressure;
+    /// Like [get_particulate_filter_pressure_bank_1](Self::get_particulate_filter_pressure_bank_1)
+    /// but for bank 2
+    fn get_particulate_filter_pressure_bank_2(0x01, 0x7B) -> ParticulateFilterPressure;
+
+    /// Get the Diesel particulate filter temperatures
+    fn get_particulate_filter_temperatures(0x01, 0x7C) -> ParticulateFilterTemperatures;
+
+    /// Get where the engine is relative to the NOx NTE (not-to-exceed) control area
+    fn get_nox_nte_status<u8>(0x01, 0x7D, |v: u8| NteStatus::from(v)) -> NteStatus;
+
+    /// Get where the engine is relative to the PM (particulate matter) NTE (not-to-exceed)
+    /// control area
+    fn get_pm_nte_status<u8>(0x01, 0x7E, |v: u8| NteStatus::from(v)) -> NteStatus;
+
+    /// Get the total engine run time, and the time spent idling and with power take-off active
+    fn get_engine_run_time(0x01, 0x7F) -> EngineRunTime;
+
+    /// Get service 1 PID support for $81 to $A0
+    fn get_service_1_pid_support_5(0x01, 0x80) -> u32;
+
+    /// Get the engine run time with emission increasing AECDs (auxiliary emission control
+    /// devices) 1 to 5 active
+    fn get_aecd_run_time_1_5(0x01, 0x81) -> AecdRunTime;
+    /// Like [get_aecd_run_time_1_5](Self::get_aecd_run_time_1_5) but for AECDs 6 to 10
+    fn get_aecd_run_time_6_10(0x01, 0x82) -> AecdRunTime;
+
+    /// Get the NOx concentrations measured by the NOx sensors
+    fn get_nox_sensors(0x01, 0x83) -> NoxSensors;
+
+    /// Get the manifold surface temperature in ºC
+    fn get_manifold_surface_temperature<u8>(0x01, 0x84, |v: i16| v - 40) -> i16;
+
+    /// Get the reagent consumption and tank level of the NOx reagent system
+    fn get_nox_reagent_system(0x01, 0x85) -> NoxReagentSystem;
+
+    /// Get the particulate matter concentrations measured by the PM sensors
+    fn get_particulate_matter_sensors(0x01, 0x86) -> ParticulateMatterSensors;
+
+    /// Get the absolute intake manifold pressures of sensors A and B
+    fn get_intake_manifold_pressure_sensors(0x01, 0x87) -> IntakeManifoldPressureSensors;
+
+    /// Get the state and history of the SCR (selective catalytic reduction) inducement system
+    fn get_scr_inducement_system(0x01, 0x88) -> ScrInducementSystem;
+
+    /// Get the two run time counters of emission increasing AECDs (auxiliary emission control
+    /// devices) 11 to 15
+    fn get_aecd_timers_11_15(0x01, 0x89) -> AecdTimers;
+    /// Like [get_aecd_timers_11_15](Self::get_aecd_timers_11_15) but for AECDs 16 to 20
+    fn get_aecd_timers_16_20(0x01, 0x8A) -> AecdTimers;
+
+    /// Get the status of Diesel particulate filter regeneration
+    fn get_diesel_aftertreatment(0x01, 0x8B) -> DieselAftertreatment;
+
+    /// Get the equivalence ratios (lambda) of the wide range oxygen sensors
+    fn get_wide_range_oxygen_sensors(0x01, 0x8C) -> WideRangeOxygenSensors;
+
+    /// Get the relative throttle position G in %
+    fn get_throttle_position_g<u8>(0x01, 0x8D, |v: f32| v * 100. / 255.) -> f32;
+
+    /// Get the engine friction torque in %, relative to the [engine reference
+    /// torque](Self::get_engine_reference_torque)
+    fn get_engine_friction_torque<u8>(0x01, 0x8E, |v: i16| v - 125) -> i16;
+
+    /// Get the state and output of the particulate matter sensors
+    fn get_particulate_matter_sensor_output(0x01, 0x8F) -> ParticulateMatterSensorOutput;
+
+    /// Get the WWH-OBD state of the malfunction indicator and readiness of the whole vehicle
+    fn get_vehicle_obd_system_information(0x01, 0x90) -> VehicleObdSystemInformation;
+
+    /// Get the WWH-OBD readiness and malfunction indicator time of each ECU
+    fn get_ecu_obd_system_information(0x01, 0x91) -> EcuObdSystemInformation;
+
+    /// Get whether the control loops of the compression ignition fuel systems are closed
+    fn get_fuel_system_control(0x01, 0x92) -> FuelSystemControl;
+
+    /// Get the WWH-OBD counters of the whole vehicle
+    fn get_vehicle_obd_counters(0x01, 0x93) -> VehicleObdCounters;
+
+    /// Get the state and fault times of the NOx warning and inducement system
+    fn get_nox_warning_system(0x01, 0x94) -> NoxWarningSystem;
+
+    /// Get the exhaust gas temperatures of bank 1 sensors 5 to 8
+    ///
+    /// The fields `sensor_1` to `sensor_4` hold sensors 5 to 8.
+    fn get_exhaust_gas_temperatures_bank_1_5_8(0x01, 0x98) -> ExhaustGasTemperatures;
+    /// Like [get_exhaust_gas_temperatures_bank_1_5_8](Self::get_exhaust_gas_temperatures_bank_1_5_8)
+    /// but for bank 2
+    fn get_exhaust_gas_temperatures_bank_2_5_8(0x01, 0x99) -> ExhaustGasTemperatures;
+
+    /// Get the state, voltage and current of the hybrid or electric vehicle battery system
+    fn get_hybrid_battery_system(0x01, 0x9A) -> HybridBatterySystem;
+
+    /// Get the type, concentration, temperature and level of the Diesel exhaust fluid
+    fn get_diesel_exhaust_fluid_sensor(0x01, 0x9B) -> DieselExhaustFluidSensor;
+
+    /// Get the oxygen concentrations measured by the oxygen sensors
+    fn get_oxygen_sensor_concentrations(0x01, 0x9C) -> OxygenSensorConcentrations;
+
+    /// Get the fuel rates of the engine and of the whole vehicle
+    fn get_fuel_rates(0x01, 0x9D) -> FuelRates;
+
+    /// Get the engine exhaust flow rate in kg/h
+    fn get_exhaust_flow_rate<u16>(0x01, 0x9E, |v: f32| v * 0.2) -> f32;
+
+    /// Get how much each bank uses each fuel system
+    fn get_fuel_system_percentage_use(0x01, 0x9F) -> FuelSystemUse;
+
+    /// Get service 1 PID support for $A1 to $C0
+    fn get_service_1_pid_support_6(0x01, 0xA0) -> u32;
+
+    /// Get the NOx concentrations measured by the NOx sensors, corrected for the ambient
+    /// conditions
+    fn get_nox_sensors_corrected(0x01, 0xA1) -> NoxSensors;
+
+    /// Get the fuel rate per cylinder in mg/stroke
+    fn get_cylinder_fuel_rate<u16>(0x01, 0xA2, |v: f32| v / 32.) -> f32;
+
+    /// Get the evaporative system vapor pressures of sensors A and B
+    fn get_evap_system_vapor_pressure_sensors(0x01, 0xA3) -> EvapSystemVaporPressure;
+
+    /// Get the actual transmission gear and gear ratio
+    fn get_transmission_gear(0x01, 0xA4) -> TransmissionGear;
+
+    /// Get the commanded Diesel exhaust fluid dosing
+    fn get_diesel_exhaust_fluid_dosing(0x01, 0xA5) -> DieselExhaustFluidDosing;
+
+    /// Get the odometer reading in km
+    fn get_odometer<u32>(0x01, 0xA6, |v: f64| v / 10.) -> f64;
+
+    /// Get the NOx concentrations measured by NOx sensors 3 and 4
+    fn get_nox_sensors_3_4(0x01, 0xA7) -> NoxSensors3And4;
+
+    /// Get the NOx concentrations measured by NOx sensors 3 and 4, corrected for the ambient
+    /// conditions
+    fn get_nox_sensors_3_4_corrected(0x01, 0xA8) -> NoxSensors3And4;
+
+    /// Get the state of the ABS disable switch
+    fn get_abs_disable_switch(0x01, 0xA9) -> AbsDisableSwitch;
+
+    /// Get service 1 PID support for $C1 to $E0
+    fn get_service_1_pid_support_7(0x01, 0xC0) -> u32;
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{device::Mock, Obd2};
+
+    /// A device whose ECU answers each service 01 PID with the given reply
+    pub(super) fn device(replies: &[(u8, &str)]) -> Obd2<Mock> {
+        let mut mock = Mock::new();
+        // without the supported PIDs, every PID is requested
+        mock.expect(&[0x01, 0x00], "NO DATA");
+        for (pid, reply) in replies {
+            mock.expect(&[0x01, *pid], reply);
+        }
+        Obd2::new(mock)
+    }
+
+    pub(super) fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn pids_21_to_40() {
+        let mut device = device(&[
+            (0x21, "41 21 01 2C"),
+            (0x22, "41 22 03 E8"),
+            (0x23, "41 23 01 F4"),
+            (0x24, "41 24 80 00 40 00"),
+            (0x2D, "41 2D 40"),
+            (0x32, "41 32 FF 38"),
+            (0x34, "41 34 80 00 7F 80"),
+            (0x3C, "41 3C 0F A0"),
+        ]);
+
+        assert_eq!(device.get_distance_with_mil_on().unwrap(), [300]);
+        // 0.079 kPa per bit
+        assert_close(device.get_fuel_rail_pressure().unwrap()[0], 79.);
+        // 10 kPa per bit
+        assert_close(device.get_fuel_rail_gauge_pressure().unwrap()[0], 5000.);
+        let sensor = device.get_wide_range_oxygen_sensor_1_voltage().unwrap()[0];
+        assert_close(sensor.equivalence_ratio, 1.);
+        assert_close(sensor.voltage, 2.);
+        // 128 is no error
+        assert_close(device.get_egr_error().unwrap()[0], -50.);
+        // signed, 0.25 Pa per bit
+        assert_close(device.get_evap_system_vapor_pressure().unwrap()[0], -50.);
+        // the current is offset by 128 mA
+        let sensor = device.get_wide_range_oxygen_sensor_1_current().unwrap()[0];
+        assert_close(sensor.equivalence_ratio, 1.);
+        assert_close(sensor.current, -0.5);
+        assert_close(
+            device.get_catalyst_temperature_bank_1_sensor_1().unwrap()[0],
+            360.,
+        );
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn pids_41_to_60() {
+        let mut device = device(&[
+            (0x42, "41 42 30 D4"),
+            (0x44, "41 44 80 00"),
+            (0x46, "41 46 00"),
+            (0x4F, "41 4F 02 05 80 0A"),
+            (0x50, "41 50 64 00 00 00"),
+            (0x51, "41 51 04"),
+            (0x51, "41 51 42"),
+            (0x53, "41 53 4E 20"),
+            (0x54, "41 54 FC 18"),
+            (0x55, "41 55 40 C0"),
+            (0x59, "41 59 01 F4"),
+            (0x5D, "41 5D 6A 00"),
+            (0x5E, "41 5E 00 C8"),
+        ]);
+
+        assert_close(device.get_control_module_voltage().unwrap()[0], 12.5);
+        assert_close(device.get_commanded_equivalence_ratio().unwrap()[0], 1.);
+        assert_eq!(device.get_ambient_air_temperature().unwrap(), [-40]);
+        let maximum = device.get_maximum_values().unwrap()[0];
+        assert_eq!(maximum.equivalence_ratio, 2);
+        assert_eq!(maximum.oxygen_sensor_voltage, 5);
+        assert_eq!(maximum.oxygen_sensor_current, 128);
+        assert_eq!(maximum.intake_manifold_pressure, 100);
+        // only the first byte is used, at 10 g/s per bit
+        assert_eq!(device.get_maximum_air_flow_rate().unwrap(), [1000]);
+        assert_eq!(device.get_fuel_type().unwrap(), [FuelType::Diesel]);
+        assert_eq!(device.get_fuel_type().unwrap(), [FuelType::Other(0x42)]);
+        // 0.005 kPa per bit
+        assert_close(
+            device.get_absolute_evap_system_vapor_pressure().unwrap()[0],
+            100.,
+        );
+        // signed, 1 Pa per bit
+        assert_eq!(
+            device.get_evap_system_vapor_pressure_wide().unwrap(),
+            [-1000]
+        );
+        // one trim for each bank, where 128 is no trim
+        let trims = device.get_short_term_secondary_oxygen_trim_1_3().unwrap()[0];
+        assert_close(trims[0], -0.5);
+        assert_close(trims[1], 0.5);
+        // 10 kPa per bit
+        assert_close(device.get_fuel_rail_absolute_pressure().unwrap()[0], 5000.);
+        assert_close(device.get_fuel_injection_timing().unwrap()[0], 2.);
+        assert_close(device.get_engine_fuel_rate().unwrap()[0], 10.);
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn dtc_info_packed_fields() {
+        let mut device = device(&[(0x01, "41 01 81 37 65 04")]);
+
+        // the deprecated fields keep their original layout
+        let info = device.get_dtc_info().unwrap().remove(0);
+        assert_eq!(info.common_test_availability, 0x1F);
+        assert_eq!(info.specific_test_availability, 0x0465);
+        assert!(!info.is_compression_engine);
+        assert_eq!(
+            info.readiness.incomplete().collect::<Vec<_>>(),
+            [
+                ReadinessMonitor::Misfire,
+                ReadinessMonitor::FuelSystem,
+                ReadinessMonitor::Evap
+            ]
+        );
+        assert!(device.device().verify().is_ok());
+    }
 }
@@ -0,0 +1,182 @@
+//! Decoding of oxygen sensor monitoring (service 05) and on-board monitoring (service 06) test
+//! results
+
+/// How to convert the raw value of a test to a physical value
+pub(super) struct Scaling {
+    /// Whether the raw value is a two's complement signed number
+    pub signed: bool,
+    /// The size of one step of the raw value
+    pub scale: f64,
+    /// Added after scaling
+    pub offset: f64,
+    /// The unit of the physical value, empty if it has none
+    pub unit: &'static str,
+}
+
+/// Look up a Unit and Scaling ID (SAE J1979 appendix E)
+///
+/// Unknown IDs are treated as raw values, signed if the ID is `0x80` or more.
+pub(super) fn unit_and_scaling(id: u8) -> Scaling {
+    let (scale, offset, unit) = match id {
+        0x01 | 0x81 => (1., 0., ""),
+        0x02 | 0x82 => (0.1, 0., ""),
+        0x03 | 0x83 => (0.01, 0., ""),
+        0x04 | 0x84 => (0.001, 0., ""),
+        0x05 | 0x85 => (0.0000305, 0., ""),
+        0x06 | 0x86 => (0.000305, 0., ""),
+        0x07 => (0.25, 0., "rpm"),
+        0x08 => (0.01, 0., "km/h"),
+        0x09 => (1., 0., "km/h"),
+        0x0A | 0x8A => (0.122, 0., "mV"),
+        0x0B | 0x8B => (0.001, 0., "V"),
+        0x0C | 0x8C => (0.01, 0., "V"),
+        0x0D | 0x8D => (0.00390625, 0., "mA"),
+        0x0E | 0x8E => (0.001, 0., "A"),
+        0x0F => (0.01, 0., "A"),
+        0x10 | 0x90 => (1., 0., "ms"),
+        0x11 => (100., 0., "ms"),
+        0x12 => (1., 0., "s"),
+        0x13 => (1., 0., "mΩ"),
+        0x14 => (1., 0., "Ω"),
+        0x15 => (1., 0., "kΩ"),
+        0x16 => (0.1, -40., "°C"),
+        0x96 => (0.1, 0., "°C"),
+        0x17 => (0.01, 0., "kPa"),
+        0x18 => (0.0117, 0., "kPa"),
+        0x19 => (0.079, 0., "kPa"),
+        0x1A => (1., 0., "kPa"),
+        0x1B => (10., 0., "kPa"),
+        0x1C | 0x9C => (0.01, 0., "°"),
+        0x1D | 0x9D => (0.5, 0., "°"),
+        0x1E => (0.0000305, 0., "λ"),
+        0x1F => (0.05, 0., "A/F"),
+        0x20 => (0.0039062, 0., ""),
+        0x21 => (1., 0., "mHz"),
+        0x22 => (1., 0., "Hz"),
+        0x23 => (1., 0., "kHz"),
+        0x24 => (1., 0., "counts"),
+        0x25 => (1., 0., "km"),
+        0x26 => (0.1, 0., "mV/ms"),
+        0x27 => (0.01, 0., "g/s"),
+        0x28 | 0xA8 => (1., 0., "g/s"),
+        0x29 | 0xA9 => (0.25, 0., "Pa/s"),
+        0x2A => (0.001, 0., "kg/h"),
+        0x2B => (1., 0., "switches"),
+        0x2C => (0.01, 0., "g/cyl"),
+        0x2D | 0xAD => (0.01, 0., "mg/stroke"),
+        0x2E => (1., 0., ""),
+        0x2F | 0xAF => (0.01, 0., "%"),
+        0x30 | 0xB0 => (0.001526, 0., "%"),
+        0x31 => (0.001, 0., "L"),
+        0x32 => (0.0000305, 0., "in"),
+        0x33 => (0.00024414, 0., "λ"),
+        0x34 => (1., 0., "min"),
+        0x35 => (10., 0., "ms"),
+        0x36 => (0.01, 0., "g"),
+        0x37 => (0.1, 0., "g"),
+        0x38 => (1., 0., "g"),
+        0x39 => (0.01, -327.68, "%"),
+        0x3A => (0.001, 0., "g"),
+        0x3B => (0.0001, 0., "g"),
+        0x3C => (0.1, 0., "µs"),
+        0x3D => (0.01, 0., "mA"),
+        0x3E => (0.00006103516, 0., "mm²"),
+        0x3F => (0.01, 0., "L"),
+        0x40 => (1., 0., "ppm"),
+        0x41 => (0.01, 0., "µA"),
+        0xAE => (0.1, 0., "mg/stroke"),
+        0xB1 => (2., 0., "mV/s"),
+        0xFC => (0.01, 0., "kPa"),
+        0xFD => (0.001, 0., "kPa"),
+        0xFE => (0.25, 0., "Pa"),
+        _ => (1., 0., ""),
+    };
+
+    Scaling {
+        signed: id >= 0x80,
+        scale,
+        offset,
+        unit,
+    }
+}
+
+/// Get the name of a standard OBDMID (on-board diagnostic monitor ID)
+pub(super) fn monitor_name(mid: u8) -> Option<String> {
+    // the bank and sensor of a monitor that has one ID for each sensor of four banks
+    let sensor = |first: u8| {
+        format!(
+            "bank {} sensor {}",
+            (mid - first) / 4 + 1,
+            (mid - first) % 4 + 1
+        )
+    };
+    // the bank of a monitor that has one ID for each bank
+    let bank = |first: u8| format!("bank {}", mid - first + 1);
+
+    Some(match mid {
+        0x01..=0x10 => format!("Oxygen sensor monitor {}", sensor(0x01)),
+        0x21..=0x24 => format!("Catalyst monitor {}", bank(0x21)),
+        0x31..=0x34 => format!("EGR monitor {}", bank(0x31)),
+        0x35..=0x38 => format!("VVT monitor {}", bank(0x35)),
+        0x39 => "EVAP monitor (cap off / 0.150\")".to_owned(),
+        0x3A => "EVAP monitor (0.090\")".to_owned(),
+        0x3B => "EVAP monitor (0.040\")".to_owned(),
+        0x3C => "EVAP monitor (0.020\")".to_owned(),
+        0x3D => "Purge flow monitor".to_owned(),
+        0x41..=0x50 => format!("Oxygen sensor heater monitor {}", sensor(0x41)),
+        0x61..=0x64 => format!("Heated catalyst monitor {}", bank(0x61)),
+        0x71..=0x74 => format!("Secondary air monitor {}", mid - 0x70),
+        0x81..=0x84 => format!("Fuel system monitor {}", bank(0x81)),
+        0x85..=0x86 => format!("Boost pressure control monitor {}", bank(0x85)),
+        0x90..=0x91 => format!("NOx adsorber monitor {}", bank(0x90)),
+        0x98..=0x99 => format!("NOx catalyst monitor {}", bank(0x98)),
+        0xA1 => "Misfire monitor general data".to_owned(),
+        0xA2..=0xAD => format!("Misfire cylinder {} data", mid - 0xA1),
+        0xB0..=0xB1 => format!("PM filter monitor {}", bank(0xB0)),
+        _ => return None,
+    })
+}
+
+/// Get the name, scale, and unit of a standard oxygen sensor test (service 05)
+///
+/// Unknown TIDs, including the manufacturer specific ones from `0x21`, are `None`.
+pub(super) fn oxygen_sensor_test(tid: u8) -> Option<(&'static str, f32, &'static str)> {
+    Some(match tid {
+        0x01 => ("Rich to lean sensor threshold voltage", 0.005, "V"),
+        0x02 => ("Lean to rich sensor threshold voltage", 0.005, "V"),
+        0x03 => ("Low sensor voltage for switch time calculation", 0.005, "V"),
+        0x04 => (
+            "High sensor voltage for switch time calculation",
+            0.005,
+            "V",
+        ),
+        0x05 => ("Rich to lean sensor switch time", 0.004, "s"),
+        0x06 => ("Lean to rich sensor switch time", 0.004, "s"),
+        0x07 => ("Minimum sensor voltage for test cycle", 0.005, "V"),
+        0x08 => ("Maximum sensor voltage for test cycle", 0.005, "V"),
+        0x09 => ("Time between sensor transitions", 0.04, "s"),
+        0x0A => ("Sensor period", 0.04, "s"),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::MonitorTestResult;
+
+    #[test]
+    fn temperature_scaling() {
+        // 0x16 is unsigned with a -40 °C offset: 1000 is 60 °C
+        let result =
+            MonitorTestResult::from_record(&[0x21, 0x01, 0x16, 0x03, 0xE8, 0x00, 0x00, 0xFF, 0xFF]);
+        assert!((result.value - 60.).abs() < 1e-9);
+        assert_eq!(result.unit, "°C");
+
+        // 0x96 is signed without an offset: -400 is -40 °C
+        let result =
+            MonitorTestResult::from_record(&[0x21, 0x01, 0x96, 0xFE, 0x70, 0x80, 0x00, 0x7F, 0xFF]);
+        assert!((result.value + 40.).abs() < 1e-9);
+        assert!((result.min + 3276.8).abs() < 1e-9);
+        assert!(result.passed);
+    }
+}
@@ -0,0 +1,905 @@
+//! Service 01 PIDs made of several values, each of which may or may not be supported
+//!
+//! The first byte of these PIDs is a bit field saying which of the following values the ECU
+//! supports. The values that are not supported are `None`.
+
+use std::collections::BTreeMap;
+
+use crate::{EcuAddress, Obd2Device, Result};
+
+use super::GetObd2Values;
+
+/// A raw value in a record, read from the start of a slice
+trait Field {
+    fn read(bytes: &[u8]) -> Self;
+}
+
+impl Field for u8 {
+    fn read(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl Field for u16 {
+    fn read(bytes: &[u8]) -> Self {
+        u16::from_be_bytes([bytes[0], bytes[1]])
+    }
+}
+
+impl Field for i16 {
+    fn read(bytes: &[u8]) -> Self {
+        i16::from_be_bytes([bytes[0], bytes[1]])
+    }
+}
+
+impl Field for u32 {
+    fn read(bytes: &[u8]) -> Self {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+impl Field for [u32; 2] {
+    fn read(bytes: &[u8]) -> Self {
+        [u32::read(bytes), u32::read(&bytes[4..])]
+    }
+}
+
+record! {
+    /// The status of auxiliary inputs and outputs, from PID `0x65`
+    pub struct AuxiliaryInputOutput[2] {
+        /// Whether power take-off is active
+        power_take_off: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+        /// Whether an automatic transmission is in neutral (or park)
+        automatic_transmission_neutral: bool = bit 1, <u8>(1, |v: u8| v & 0x02 != 0),
+        /// Whether a manual transmission is in neutral (or the clutch is pressed)
+        manual_transmission_neutral: bool = bit 2, <u8>(1, |v: u8| v & 0x04 != 0),
+        /// Whether the glow plug lamp is on
+        glow_plug_lamp: bool = bit 3, <u8>(1, |v: u8| v & 0x08 != 0),
+    }
+}
+
+record! {
+    /// The air flow rates of the mass air flow sensors in g/s, from PID `0x66`
+    pub struct MassAirFlowSensors[5] {
+        /// Sensor A
+        sensor_a: f32 = bit 0, <u16>(1, |v: f32| v / 32.),
+        /// Sensor B
+        sensor_b: f32 = bit 1, <u16>(3, |v: f32| v / 32.),
+    }
+}
+
+record! {
+    /// The engine coolant temperatures in ºC, from PID `0x67`
+    pub struct CoolantTemperatureSensors[3] {
+        /// Sensor 1
+        sensor_1: i16 = bit 0, <u8>(1, |v: i16| v - 40),
+        /// Sensor 2
+        sensor_2: i16 = bit 1, <u8>(2, |v: i16| v - 40),
+    }
+}
+
+record! {
+    /// The intake air temperatures in ºC, from PID `0x68`
+    pub struct IntakeAirTemperatureSensors[7] {
+        /// Bank 1, sensor 1
+        bank_1_sensor_1: i16 = bit 0, <u8>(1, |v: i16| v - 40),
+        /// Bank 1, sensor 2
+        bank_1_sensor_2: i16 = bit 1, <u8>(2, |v: i16| v - 40),
+        /// Bank 1, sensor 3
+        bank_1_sensor_3: i16 = bit 2, <u8>(3, |v: i16| v - 40),
+        /// Bank 2, sensor 1
+        bank_2_sensor_1: i16 = bit 3, <u8>(4, |v: i16| v - 40),
+        /// Bank 2, sensor 2
+        bank_2_sensor_2: i16 = bit 4, <u8>(5, |v: i16| v - 40),
+        /// Bank 2, sensor 3
+        bank_2_sensor_3: i16 = bit 5, <u8>(6, |v: i16| v - 40),
+    }
+}
+
+record! {
+    /// The commanded and actual EGR (exhaust gas recirculation) in %, from PID `0x69`
+    pub struct EgrControl[7] {
+        /// Commanded EGR A duty cycle or position
+        commanded_a: f32 = bit 0, <u8>(1, |v: f32| v * 100. / 255.),
+        /// Actual EGR A duty cycle or position
+        actual_a: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// EGR A error, relative to the commanded value
+        error_a: f32 = bit 2, <u8>(3, |v: f32| (v - 128.) * 100. / 128.),
+        /// Commanded EGR B duty cycle or position
+        commanded_b: f32 = bit 3, <u8>(4, |v: f32| v * 100. / 255.),
+        /// Actual EGR B duty cycle or position
+        actual_b: f32 = bit 4, <u8>(5, |v: f32| v * 100. / 255.),
+        /// EGR B error, relative to the commanded value
+        error_b: f32 = bit 5, <u8>(6, |v: f32| (v - 128.) * 100. / 128.),
+    }
+}
+
+record! {
+    /// The commanded and relative position of the Diesel intake air flow control in %, from PID
+    /// `0x6A`
+    pub struct IntakeAirFlowControl[5] {
+        /// Commanded intake air flow A control
+        commanded_a: f32 = bit 0, <u8>(1, |v: f32| v * 100. / 255.),
+        /// Relative intake air flow A position
+        position_a: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// Commanded intake air flow B control
+        commanded_b: f32 = bit 2, <u8>(3, |v: f32| v * 100. / 255.),
+        /// Relative intake air flow B position
+        position_b: f32 = bit 3, <u8>(4, |v: f32| v * 100. / 255.),
+    }
+}
+
+record! {
+    /// The EGR (exhaust gas recirculation) temperatures in ºC, from PID `0x6B`
+    pub struct EgrTemperatureSensors[5] {
+        /// Bank 1, sensor 1
+        bank_1_sensor_1: i16 = bit 0, <u8>(1, |v: i16| v - 40),
+        /// Bank 1, sensor 2
+        bank_1_sensor_2: i16 = bit 1, <u8>(2, |v: i16| v - 40),
+        /// Bank 2, sensor 1
+        bank_2_sensor_1: i16 = bit 2, <u8>(3, |v: i16| v - 40),
+        /// Bank 2, sensor 2
+        bank_2_sensor_2: i16 = bit 3, <u8>(4, |v: i16| v - 40),
+    }
+}
+
+record! {
+    /// The commanded and relative throttle positions in %, from PID `0x6C`
+    pub struct ThrottleActuatorControl[5] {
+        /// Commanded throttle actuator A
+        commanded_a: f32 = bit 0, <u8>(1, |v: f32| v * 100. / 255.),
+        /// Relative throttle A position
+        position_a: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// Commanded throttle actuator B
+        commanded_b: f32 = bit 2, <u8>(3, |v: f32| v * 100. / 255.),
+        /// Relative throttle B position
+        position_b: f32 = bit 3, <u8>(4, |v: f32| v * 100. / 255.),
+    }
+}
+
+record! {
+    /// The fuel pressure control system, from PID `0x6D`
+    ///
+    /// Pressures are in kPa, and temperatures in ºC.
+    pub struct FuelPressureControl[11] {
+        /// Commanded fuel rail pressure A
+        commanded_pressure_a: f32 = bit 0, <u16>(1, |v: f32| v * 10.),
+        /// Fuel rail pressure A
+        pressure_a: f32 = bit 1, <u16>(3, |v: f32| v * 10.),
+        /// Fuel temperature A
+        temperature_a: i16 = bit 2, <u8>(5, |v: i16| v - 40),
+        /// Commanded fuel rail pressure B
+        commanded_pressure_b: f32 = bit 3, <u16>(6, |v: f32| v * 10.),
+        /// Fuel rail pressure B
+        pressure_b: f32 = bit 4, <u16>(8, |v: f32| v * 10.),
+        /// Fuel temperature B
+        temperature_b: i16 = bit 5, <u8>(10, |v: i16| v - 40),
+    }
+}
+
+record! {
+    /// The injection pressure control system in kPa, from PID `0x6E`
+    pub struct InjectionPressureControl[9] {
+        /// Commanded injection control pressure A
+        commanded_pressure_a: f32 = bit 0, <u16>(1, |v: f32| v * 10.),
+        /// Injection control pressure A
+        pressure_a: f32 = bit 1, <u16>(3, |v: f32| v * 10.),
+        /// Commanded injection control pressure B
+        commanded_pressure_b: f32 = bit 2, <u16>(5, |v: f32| v * 10.),
+        /// Injection control pressure B
+        pressure_b: f32 = bit 3, <u16>(7, |v: f32| v * 10.),
+    }
+}
+
+record! {
+    /// The turbocharger compressor inlet pressures in kPa, from PID `0x6F`
+    pub struct TurbochargerInletPressure[3] {
+        /// Sensor A
+        sensor_a: u8 = bit 0, <u8>(1, |v: u8| v),
+        /// Sensor B
+        sensor_b: u8 = bit 1, <u8>(2, |v: u8| v),
+    }
+}
+
+record! {
+    /// The boost pressure control system, from PID `0x70`
+    ///
+    /// Pressures are absolute, in kPa. Each status is `1` for open loop, `2` for closed loop, or
+    /// `3` if there is a fault.
+    pub struct BoostPressureControl[10] {
+        /// Commanded boost pressure A
+        commanded_pressure_a: f32 = bit 0, <u16>(1, |v: f32| v / 32.),
+        /// Boost pressure sensor A
+        pressure_a: f32 = bit 1, <u16>(3, |v: f32| v / 32.),
+        /// Commanded boost pressure B
+        commanded_pressure_b: f32 = bit 2, <u16>(5, |v: f32| v / 32.),
+        /// Boost pressure sensor B
+        pressure_b: f32 = bit 3, <u16>(7, |v: f32| v / 32.),
+        /// Boost pressure control status A
+        status_a: u8 = bit 4, <u8>(9, |v: u8| v & 0x03),
+        /// Boost pressure control status B
+        status_b: u8 = bit 5, <u8>(9, |v: u8| (v >> 2) & 0x03),
+    }
+}
+
+record! {
+    /// The VGT (variable geometry turbocharger) control system, from PID `0x71`
+    ///
+    /// Positions are in %. Each status is `1` for open loop, `2` for closed loop, or `3` if there
+    /// is a fault.
+    pub struct VgtControl[6] {
+        /// Commanded VGT A position
+        commanded_a: f32 = bit 0, <u8>(1, |v: f32| v * 100. / 255.),
+        /// VGT A position
+        position_a: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// Commanded VGT B position
+        commanded_b: f32 = bit 2, <u8>(3, |v: f32| v * 100. / 255.),
+        /// VGT B position
+        position_b: f32 = bit 3, <u8>(4, |v: f32| v * 100. / 255.),
+        /// VGT A control status
+        status_a: u8 = bit 4, <u8>(5, |v: u8| v & 0x03),
+        /// VGT B control status
+        status_b: u8 = bit 5, <u8>(5, |v: u8| (v >> 2) & 0x03),
+    }
+}
+
+record! {
+    /// The wastegate control system in %, from PID `0x72`
+    pub struct WastegateControl[5] {
+        /// Commanded wastegate A position
+        commanded_a: f32 = bit 0, <u8>(1, |v: f32| v * 100. / 255.),
+        /// Wastegate A position
+        position_a: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// Commanded wastegate B position
+        commanded_b: f32 = bit 2, <u8>(3, |v: f32| v * 100. / 255.),
+        /// Wastegate B position
+        position_b: f32 = bit 3, <u8>(4, |v: f32| v * 100. / 255.),
+    }
+}
+
+record! {
+    /// The exhaust pressures in kPa, from PID `0x73`
+    pub struct ExhaustPressure[5] {
+        /// Bank 1
+        bank_1: f32 = bit 0, <u16>(1, |v: f32| v * 0.01),
+        /// Bank 2
+        bank_2: f32 = bit 1, <u16>(3, |v: f32| v * 0.01),
+    }
+}
+
+record! {
+    /// The turbocharger speeds in RPM, from PID `0x74`
+    pub struct TurbochargerSpeed[5] {
+        /// Turbocharger A
+        turbocharger_a: u32 = bit 0, <u16>(1, |v: u32| v * 10),
+        /// Turbocharger B
+        turbocharger_b: u32 = bit 1, <u16>(3, |v: u32| v * 10),
+    }
+}
+
+record! {
+    /// The temperatures of one turbocharger in ºC, from PID `0x75` (turbocharger A) or `0x76`
+    /// (turbocharger B)
+    pub struct TurbochargerTemperatures[7] {
+        /// Compressor inlet
+        compressor_inlet: i16 = bit 0, <u8>(1, |v: i16| v - 40),
+        /// Compressor outlet
+        compressor_outlet: i16 = bit 1, <u8>(2, |v: i16| v - 40),
+        /// Turbine inlet
+        turbine_inlet: f32 = bit 2, <u16>(3, |v: f32| v / 10. - 40.),
+        /// Turbine outlet
+        turbine_outlet: f32 = bit 3, <u16>(5, |v: f32| v / 10. - 40.),
+    }
+}
+
+record! {
+    /// The charge air cooler temperatures in ºC, from PID `0x77`
+    pub struct ChargeAirCoolerTemperatures[5] {
+        /// Bank 1, sensor 1
+        bank_1_sensor_1: i16 = bit 0, <u8>(1, |v: i16| v - 40),
+        /// Bank 1, sensor 2
+        bank_1_sensor_2: i16 = bit 1, <u8>(2, |v: i16| v - 40),
+        /// Bank 2, sensor 1
+        bank_2_sensor_1: i16 = bit 2, <u8>(3, |v: i16| v - 40),
+        /// Bank 2, sensor 2
+        bank_2_sensor_2: i16 = bit 3, <u8>(4, |v: i16| v - 40),
+    }
+}
+
+record! {
+    /// The exhaust gas temperatures of one bank in ºC, from PID `0x78` (bank 1) or `0x79` (bank
+    /// 2)
+    pub struct ExhaustGasTemperatures[9] {
+        /// Sensor 1
+        sensor_1: f32 = bit 0, <u16>(1, |v: f32| v / 10. - 40.),
+        /// Sensor 2
+        sensor_2: f32 = bit 1, <u16>(3, |v: f32| v / 10. - 40.),
+        /// Sensor 3
+        sensor_3: f32 = bit 2, <u16>(5, |v: f32| v / 10. - 40.),
+        /// Sensor 4
+        sensor_4: f32 = bit 3, <u16>(7, |v: f32| v / 10. - 40.),
+    }
+}
+
+record! {
+    /// The pressures of the Diesel particulate filter of one bank in kPa, from PID `0x7A` (bank
+    /// 1) or `0x7B` (bank 2)
+    pub struct ParticulateFilterPressure[7] {
+        /// The difference between the inlet and outlet pressures
+        differential: f32 = bit 0, <i16>(1, |v: f32| v * 0.01),
+        /// Inlet pressure
+        inlet: f32 = bit 1, <u16>(3, |v: f32| v * 0.01),
+        /// Outlet pressure
+        outlet: f32 = bit 2, <u16>(5, |v: f32| v * 0.01),
+    }
+}
+
+record! {
+    /// The temperatures of the Diesel particulate filter in ºC, from PID `0x7C`
+    pub struct ParticulateFilterTemperatures[9] {
+        /// Bank 1 inlet
+        bank_1_inlet: f32 = bit 0, <u16>(1, |v: f32| v / 10. - 40.),
+        /// Bank 1 outlet
+        bank_1_outlet: f32 = bit 1, <u16>(3, |v: f32| v / 10. - 40.),
+        /// Bank 2 inlet
+        bank_2_inlet: f32 = bit 2, <u16>(5, |v: f32| v / 10. - 40.),
+        /// Bank 2 outlet
+        bank_2_outlet: f32 = bit 3, <u16>(7, |v: f32| v / 10. - 40.),
+    }
+}
+
+record! {
+    /// The engine run time in seconds, from PID `0x7F`
+    ///
+    /// # Example
+    /// ```
+    /// use obd2::{commands::Obd2DataRetrieval, device::Mock, Obd2};
+    ///
+    /// let mut mock = Mock::new();
+    /// mock.expect(&[0x01, 0x00], "41 00 00 00 00 01")
+    ///     .expect(&[0x01, 0x20], "41 20 00 00 00 01")
+    ///     .expect(&[0x01, 0x40], "41 40 00 00 00 01")
+    ///     .expect(&[0x01, 0x60], "41 60 00 00 00 02")
+    ///     .expect(&[0x01, 0x7F], "41 7F 03 00 00 0E 10 00 00 01 2C 00 00 00 00");
+    ///
+    /// let mut device = Obd2::new(mock);
+    /// let run_time = device.get_engine_run_time().unwrap()[0];
+    /// assert_eq!(run_time.total, Some(3600));
+    /// assert_eq!(run_time.idle, Some(300));
+    /// assert_eq!(run_time.power_take_off, None);
+    /// ```
+    pub struct EngineRunTime[13] {
+        /// Total run time
+        total: u32 = bit 0, <u32>(1, |v: u32| v),
+        /// Time spent idling
+        idle: u32 = bit 1, <u32>(5, |v: u32| v),
+        /// Time spent with power take-off active
+        power_take_off: u32 = bit 2, <u32>(9, |v: u32| v),
+    }
+}
+
+record! {
+    /// The engine run time with emission increasing AECDs (auxiliary emission control devices)
+    /// active in seconds, from PID `0x81` (AECDs 1 to 5) or `0x82` (AECDs 6 to 10)
+    pub struct AecdRunTime[21] {
+        /// The first AECD of the PID
+        aecd_1: u32 = bit 0, <u32>(1, |v: u32| v),
+        /// The second AECD of the PID
+        aecd_2: u32 = bit 1, <u32>(5, |v: u32| v),
+        /// The third AECD of the PID
+        aecd_3: u32 = bit 2, <u32>(9, |v: u32| v),
+        /// The fourth AECD of the PID
+        aecd_4: u32 = bit 3, <u32>(13, |v: u32| v),
+        /// The fifth AECD of the PID
+        aecd_5: u32 = bit 4, <u32>(17, |v: u32| v),
+    }
+}
+
+record! {
+    /// The NOx concentrations in ppm, from PID `0x83`, or corrected for the ambient conditions
+    /// from PID `0xA1`
+    pub struct NoxSensors[9] {
+        /// Bank 1, sensor 1
+        bank_1_sensor_1: u16 = bit 0, <u16>(1, |v: u16| v),
+        /// Bank 1, sensor 2
+        bank_1_sensor_2: u16 = bit 1, <u16>(3, |v: u16| v),
+        /// Bank 2, sensor 1
+        bank_2_sensor_1: u16 = bit 2, <u16>(5, |v: u16| v),
+        /// Bank 2, sensor 2
+        bank_2_sensor_2: u16 = bit 3, <u16>(7, |v: u16| v),
+    }
+}
+
+record! {
+    /// The NOx reagent (Diesel exhaust fluid) system, from PID `0x85`
+    pub struct NoxReagentSystem[10] {
+        /// Average reagent consumption in L/h
+        average_consumption: f32 = bit 0, <u16>(1, |v: f32| v * 0.005),
+        /// Average demanded reagent consumption in L/h
+        average_demanded_consumption: f32 = bit 1, <u16>(3, |v: f32| v * 0.005),
+        /// Reagent tank level in %
+        tank_level: f32 = bit 2, <u8>(5, |v: f32| v * 100. / 255.),
+        /// Time in seconds with a NOx warning indicator active
+        warning_time: u32 = bit 3, <u32>(6, |v: u32| v),
+    }
+}
+
+record! {
+    /// The particulate matter concentrations in mg/m³, from PID `0x86`
+    pub struct ParticulateMatterSensors[5] {
+        /// Bank 1
+        bank_1: f32 = bit 0, <u16>(1, |v: f32| v * 0.0125),
+        /// Bank 2
+        bank_2: f32 = bit 1, <u16>(3, |v: f32| v * 0.0125),
+    }
+}
+
+record! {
+    /// The absolute intake manifold pressures in kPa, from PID `0x87`
+    pub struct IntakeManifoldPressureSensors[5] {
+        /// Sensor A
+        sensor_a: f32 = bit 0, <u16>(1, |v: f32| v / 32.),
+        /// Sensor B
+        sensor_b: f32 = bit 1, <u16>(3, |v: f32| v / 32.),
+    }
+}
+
+record! {
+    /// The two run time counters in seconds of emission increasing AECDs (auxiliary emission
+    /// control devices), from PID `0x89` (AECDs 11 to 15) or `0x8A` (AECDs 16 to 20)
+    ///
+    /// Each AECD counts the time it was active in two conditions, as defined by the manufacturer.
+    pub struct AecdTimers[41] {
+        /// The first AECD of the PID
+        aecd_1: [u32; 2] = bit 0, <[u32; 2]>(1, |v: [u32; 2]| v),
+        /// The second AECD of the PID
+        aecd_2: [u32; 2] = bit 1, <[u32; 2]>(9, |v: [u32; 2]| v),
+        /// The third AECD of the PID
+        aecd_3: [u32; 2] = bit 2, <[u32; 2]>(17, |v: [u32; 2]| v),
+        /// The fourth AECD of the PID
+        aecd_4: [u32; 2] = bit 3, <[u32; 2]>(25, |v: [u32; 2]| v),
+        /// The fifth AECD of the PID
+        aecd_5: [u32; 2] = bit 4, <[u32; 2]>(33, |v: [u32; 2]| v),
+    }
+}
+
+record! {
+    /// The status of the Diesel aftertreatment system, from PID `0x8B`
+    pub struct DieselAftertreatment[7] {
+        /// Whether the particulate filter is regenerating
+        regenerating: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+        /// How close the particulate filter is to triggering a regeneration in %
+        regeneration_trigger: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// Average time between regenerations in minutes
+        average_time_between_regenerations: u16 = bit 2, <u16>(3, |v: u16| v),
+        /// Average distance between regenerations in km
+        average_distance_between_regenerations: u16 = bit 3, <u16>(5, |v: u16| v),
+    }
+}
+
+record! {
+    /// The equivalence ratios (lambda) of the wide range oxygen sensors, from PID `0x8C`
+    ///
+    /// The sensors are numbered like the ones in PID `0x13`: bank 1 sensors 1 to 4, then bank 2
+    /// sensors 1 to 4.
+    pub struct WideRangeOxygenSensors[17] {
+        /// Sensor 1
+        sensor_1: f32 = bit 0, <u16>(1, |v: f32| v / 32768.),
+        /// Sensor 2
+        sensor_2: f32 = bit 1, <u16>(3, |v: f32| v / 32768.),
+        /// Sensor 3
+        sensor_3: f32 = bit 2, <u16>(5, |v: f32| v / 32768.),
+        /// Sensor 4
+        sensor_4: f32 = bit 3, <u16>(7, |v: f32| v / 32768.),
+        /// Sensor 5
+        sensor_5: f32 = bit 4, <u16>(9, |v: f32| v / 32768.),
+        /// Sensor 6
+        sensor_6: f32 = bit 5, <u16>(11, |v: f32| v / 32768.),
+        /// Sensor 7
+        sensor_7: f32 = bit 6, <u16>(13, |v: f32| v / 32768.),
+        /// Sensor 8
+        sensor_8: f32 = bit 7, <u16>(15, |v: f32| v / 32768.),
+    }
+}
+
+record! {
+    /// The output of the particulate matter sensors, from PID `0x8F`
+    ///
+    /// The output is normalized by the manufacturer, in %.
+    pub struct ParticulateMatterSensorOutput[7] {
+        /// Whether bank 1, sensor 1 is measuring
+        bank_1_active: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+        /// Whether bank 1, sensor 1 is regenerating
+        bank_1_regenerating: bool = bit 0, <u8>(1, |v: u8| v & 0x02 != 0),
+        /// Bank 1, sensor 1 output
+        bank_1_output: f32 = bit 1, <i16>(2, |v: f32| v * 0.01),
+        /// Whether bank 2, sensor 1 is measuring
+        bank_2_active: bool = bit 2, <u8>(4, |v: u8| v & 0x01 != 0),
+        /// Whether bank 2, sensor 1 is regenerating
+        bank_2_regenerating: bool = bit 2, <u8>(4, |v: u8| v & 0x02 != 0),
+        /// Bank 2, sensor 1 output
+        bank_2_output: f32 = bit 3, <i16>(5, |v: f32| v * 0.01),
+    }
+}
+
+record! {
+    /// The WWH-OBD (world-wide harmonized OBD) state of the whole vehicle, from PID `0x90`
+    pub struct VehicleObdSystemInformation[3] {
+        /// What the malfunction indicator shows: `0` off, `1` on demand, `2` short, or `3`
+        /// continuous
+        mi_display: u8 = bit 0, <u8>(1, |v: u8| v & 0x03),
+        /// The activation mode of the malfunction indicator, from `1` to `4`
+        mi_activation_mode: u8 = bit 1, <u8>(1, |v: u8| v >> 4),
+        /// Whether the monitors of every ECU are ready
+        ready: bool = bit 2, <u8>(2, |v: u8| v & 0x01 != 0),
+    }
+}
+
+record! {
+    /// The WWH-OBD (world-wide harmonized OBD) state of one ECU, from PID `0x91`
+    pub struct EcuObdSystemInformation[5] {
+        /// Whether the monitors of the ECU are ready
+        ready: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+        /// Time in hours with the malfunction indicator on continuously for DTCs of the ECU
+        continuous_mi_time: u16 = bit 1, <u16>(2, |v: u16| v),
+    }
+}
+
+record! {
+    /// Whether the control loops of the compression ignition fuel systems are closed, from PID
+    /// `0x92`
+    pub struct FuelSystemControl[2] {
+        /// Fuel system 1 pressure control
+        pressure_1: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+        /// Fuel system 1 injection quantity control
+        injection_quantity_1: bool = bit 1, <u8>(1, |v: u8| v & 0x02 != 0),
+        /// Fuel system 1 injection timing control
+        injection_timing_1: bool = bit 2, <u8>(1, |v: u8| v & 0x04 != 0),
+        /// Fuel system 1 idle fuel balance or contribution control
+        idle_fuel_balance_1: bool = bit 3, <u8>(1, |v: u8| v & 0x08 != 0),
+        /// Fuel system 2 pressure control
+        pressure_2: bool = bit 4, <u8>(1, |v: u8| v & 0x10 != 0),
+        /// Fuel system 2 injection quantity control
+        injection_quantity_2: bool = bit 5, <u8>(1, |v: u8| v & 0x20 != 0),
+        /// Fuel system 2 injection timing control
+        injection_timing_2: bool = bit 6, <u8>(1, |v: u8| v & 0x40 != 0),
+        /// Fuel system 2 idle fuel balance or contribution control
+        idle_fuel_balance_2: bool = bit 7, <u8>(1, |v: u8| v & 0x80 != 0),
+    }
+}
+
+record! {
+    /// The WWH-OBD (world-wide harmonized OBD) counters of the whole vehicle, from PID `0x93`
+    pub struct VehicleObdCounters[3] {
+        /// Cumulative time in hours with the malfunction indicator on continuously
+        continuous_mi_time: u16 = bit 0, <u16>(1, |v: u16| v),
+    }
+}
+
+record! {
+    /// The NOx warning and inducement system, from PID `0x94`
+    ///
+    /// Times are in hours, and count how long each fault was active.
+    pub struct NoxWarningSystem[12] {
+        /// Whether the NOx warning indicator is on
+        warning_active: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+        /// The inducement level, `0` for none, `1` for low level, or `2` for severe
+        inducement_level: u8 = bit 1, <u8>(1, |v: u8| (v >> 1) & 0x03),
+        /// Time with incorrect reagent quality
+        reagent_quality_time: u16 = bit 2, <u16>(2, |v: u16| v),
+        /// Time with reagent consumption interrupted
+        reagent_consumption_time: u16 = bit 3, <u16>(4, |v: u16| v),
+        /// Time with reagent dosing interrupted
+        dosing_time: u16 = bit 4, <u16>(6, |v: u16| v),
+        /// Time with the EGR valve impeded
+        egr_valve_time: u16 = bit 5, <u16>(8, |v: u16| v),
+        /// Time with a fault of the NOx monitoring system
+        monitoring_system_time: u16 = bit 6, <u16>(10, |v: u16| v),
+    }
+}
+
+record! {
+    /// The status of the hybrid or electric vehicle battery system, from PID `0x9A`
+    pub struct HybridBatterySystem[6] {
+        /// The charging state, `0` for charge sustaining, `1` for charge depleting, `2` for
+        /// charge increasing, or `3` for not applicable
+        charging_state: u8 = bit 0, <u8>(1, |v: u8| v & 0x03),
+        /// Battery voltage in V
+        voltage: f32 = bit 1, <u16>(2, |v: f32| v * 0.015625),
+        /// Battery current in A, negative while charging
+        current: f32 = bit 2, <i16>(4, |v: f32| v * 0.1),
+    }
+}
+
+record! {
+    /// The oxygen concentrations measured by the oxygen sensors in %, from PID `0x9C`
+    ///
+    /// The sensors are numbered like the ones in PID `0x13`: bank 1 sensors 1 to 4, then bank 2
+    /// sensors 1 to 4.
+    pub struct OxygenSensorConcentrations[17] {
+        /// Sensor 1
+        sensor_1: f32 = bit 0, <u16>(1, |v: f32| v * 0.001),
+        /// Sensor 2
+        sensor_2: f32 = bit 1, <u16>(3, |v: f32| v * 0.001),
+        /// Sensor 3
+        sensor_3: f32 = bit 2, <u16>(5, |v: f32| v * 0.001),
+        /// Sensor 4
+        sensor_4: f32 = bit 3, <u16>(7, |v: f32| v * 0.001),
+        /// Sensor 5
+        sensor_5: f32 = bit 4, <u16>(9, |v: f32| v * 0.001),
+        /// Sensor 6
+        sensor_6: f32 = bit 5, <u16>(11, |v: f32| v * 0.001),
+        /// Sensor 7
+        sensor_7: f32 = bit 6, <u16>(13, |v: f32| v * 0.001),
+        /// Sensor 8
+        sensor_8: f32 = bit 7, <u16>(15, |v: f32| v * 0.001),
+    }
+}
+
+record! {
+    /// How much each bank uses each fuel system in %, from PID `0x9F`
+    pub struct FuelSystemUse[9] {
+        /// Fuel system A, bank 1
+        fuel_system_a_bank_1: f32 = bit 0, <u8>(1, |v: f32| v * 100. / 255.),
+        /// Fuel system A, bank 2
+        fuel_system_a_bank_2: f32 = bit 1, <u8>(2, |v: f32| v * 100. / 255.),
+        /// Fuel system A, bank 3
+        fuel_system_a_bank_3: f32 = bit 2, <u8>(3, |v: f32| v * 100. / 255.),
+        /// Fuel system A, bank 4
+        fuel_system_a_bank_4: f32 = bit 3, <u8>(4, |v: f32| v * 100. / 255.),
+        /// Fuel system B, bank 1
+        fuel_system_b_bank_1: f32 = bit 4, <u8>(5, |v: f32| v * 100. / 255.),
+        /// Fuel system B, bank 2
+        fuel_system_b_bank_2: f32 = bit 5, <u8>(6, |v: f32| v * 100. / 255.),
+        /// Fuel system B, bank 3
+        fuel_system_b_bank_3: f32 = bit 6, <u8>(7, |v: f32| v * 100. / 255.),
+        /// Fuel system B, bank 4
+        fuel_system_b_bank_4: f32 = bit 7, <u8>(8, |v: f32| v * 100. / 255.),
+    }
+}
+
+record! {
+    /// The evaporative system vapor pressures in Pa, from PID `0xA3`
+    ///
+    /// Each sensor is reported with the scaling of PID `0x32` and with the wider range of PID
+    /// `0x54`.
+    pub struct EvapSystemVaporPressure[9] {
+        /// Sensor A
+        sensor_a: f32 = bit 0, <i16>(1, |v: f32| v / 4.),
+        /// Sensor A, wide range
+        sensor_a_wide: i16 = bit 1, <i16>(3, |v: i16| v),
+        /// Sensor B
+        sensor_b: f32 = bit 2, <i16>(5, |v: f32| v / 4.),
+        /// Sensor B, wide range
+        sensor_b_wide: i16 = bit 3, <i16>(7, |v: i16| v),
+    }
+}
+
+record! {
+    /// The actual transmission gear, from PID `0xA4`
+    pub struct TransmissionGear[4] {
+        /// The gear, where `0` is neutral
+        gear: u8 = bit 1, <u8>(1, |v: u8| v >> 4),
+        /// The gear ratio
+        ratio: f32 = bit 1, <u16>(2, |v: f32| v / 1000.),
+    }
+}
+
+record! {
+    /// The commanded Diesel exhaust fluid dosing in %, from PID `0xA5`
+    pub struct DieselExhaustFluidDosing[4] {
+        /// Commanded dosing
+        commanded: f32 = bit 0, <u8>(1, |v: f32| v / 2.),
+    }
+}
+
+record! {
+    /// The NOx concentrations of sensors 3 and 4 in ppm, from PID `0xA7`, or corrected for the
+    /// ambient conditions from PID `0xA8`
+    pub struct NoxSensors3And4[9] {
+        /// Bank 1, sensor 3
+        bank_1_sensor_3: u16 = bit 0, <u16>(1, |v: u16| v),
+        /// Bank 1, sensor 4
+        bank_1_sensor_4: u16 = bit 1, <u16>(3, |v: u16| v),
+        /// Bank 2, sensor 3
+        bank_2_sensor_3: u16 = bit 2, <u16>(5, |v: u16| v),
+        /// Bank 2, sensor 4
+        bank_2_sensor_4: u16 = bit 3, <u16>(7, |v: u16| v),
+    }
+}
+
+record! {
+    /// The state of the ABS disable switch, from PID `0xA9`
+    pub struct AbsDisableSwitch[4] {
+        /// Whether the switch has disabled the ABS
+        active: bool = bit 0, <u8>(1, |v: u8| v & 0x01 != 0),
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use crate::{
+        commands::{
+            tests::{assert_close, device},
+            Obd2DataRetrieval,
+        },
+        EcuAddress,
+    };
+
+    #[test]
+    fn turbocharger_speed() {
+        let mut device = device(&[(0x74, "41 74 01 0F A0 FF FF")]);
+
+        // 10 RPM per bit
+        let speed = device.get_turbocharger_speed().unwrap()[0];
+        assert_eq!(speed.turbocharger_a, Some(40000));
+        assert_eq!(speed.turbocharger_b, None);
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn pids_8f_to_94() {
+        let mut device = device(&[
+            (0x8F, "41 8F 03 02 FF 06 01 00 64"),
+            (0x90, "41 90 07 23 01"),
+            (0x91, "41 91 02 01 01 02 00"),
+            (0x92, "41 92 0F 55"),
+            (0x93, "41 93 01 00 10"),
+            (0x94, "41 94 07 05 00 0C 00 01 00 02 00 03 00 04"),
+        ]);
+
+        let output = device.get_particulate_matter_sensor_output().unwrap()[0];
+        assert_eq!(output.bank_1_active, Some(false));
+        assert_eq!(output.bank_1_regenerating, Some(true));
+        assert_close(output.bank_1_output.unwrap(), -2.5);
+        assert_eq!(output.bank_2_active, None);
+        assert_eq!(output.bank_2_output, None);
+
+        let vehicle = device.get_vehicle_obd_system_information().unwrap()[0];
+        assert_eq!(vehicle.mi_display, Some(3));
+        assert_eq!(vehicle.mi_activation_mode, Some(2));
+        assert_eq!(vehicle.ready, Some(true));
+
+        let ecu = device.get_ecu_obd_system_information().unwrap()[0];
+        assert_eq!(ecu.ready, None);
+        assert_eq!(ecu.continuous_mi_time, Some(258));
+
+        let control = device.get_fuel_system_control().unwrap()[0];
+        assert_eq!(control.pressure_1, Some(true));
+        assert_eq!(control.injection_quantity_1, Some(false));
+        assert_eq!(control.injection_timing_1, Some(true));
+        assert_eq!(control.idle_fuel_balance_1, Some(false));
+        assert_eq!(control.pressure_2, None);
+        assert_eq!(control.idle_fuel_balance_2, None);
+
+        let counters = device.get_vehicle_obd_counters().unwrap()[0];
+        assert_eq!(counters.continuous_mi_time, Some(16));
+
+        let warning = device.get_nox_warning_system().unwrap()[0];
+        assert_eq!(warning.warning_active, Some(true));
+        assert_eq!(warning.inducement_level, Some(2));
+        assert_eq!(warning.reagent_quality_time, Some(12));
+        assert_eq!(warning.reagent_consumption_time, None);
+        assert_eq!(warning.monitoring_system_time, None);
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn pids_9c_to_c4() {
+        let mut device = device(&[
+            (
+                0x9C,
+                "41 9C 01 51 A4 00 00 00 00 00 00 00 00 00 00 00 00 00 00",
+            ),
+            (0x9F, "41 9F 11 FF 01 01 01 00 01 01 01"),
+            (0xC1, "41 C1 01 02 03"),
+        ]);
+
+        let oxygen = device.get_oxygen_sensor_concentrations().unwrap()[0];
+        assert_close(oxygen.sensor_1.unwrap(), 20.9);
+        assert_eq!(oxygen.sensor_2, None);
+        assert_eq!(oxygen.sensor_8, None);
+
+        let usage = device.get_fuel_system_percentage_use().unwrap()[0];
+        assert_close(usage.fuel_system_a_bank_1.unwrap(), 100.);
+        assert_eq!(usage.fuel_system_a_bank_2, None);
+        assert_close(usage.fuel_system_b_bank_1.unwrap(), 0.);
+        assert_eq!(usage.fuel_system_b_bank_4, None);
+
+        assert_eq!(
+            device.get_pid_c1_data().unwrap(),
+            [(EcuAddress::Unknown(0), vec![0x01, 0x02, 0x03])].into()
+        );
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn pids_81_to_8c() {
+        let mut device = device(&[
+            (
+                0x81,
+                "41 81 05 00 00 0E 10 00 00 00 01 00 00 00 78 00 00 00 00 00 00 00 00",
+            ),
+            (0x85, "41 85 0D 00 C8 00 64 FF 00 00 1C 20"),
+            (
+                0x89,
+                "41 89 02 00 00 00 01 00 00 00 02 00 00 00 0A 00 00 00 14 00 00 00 00 00 00 \
+                 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00",
+            ),
+            (0x8B, "41 8B 07 01 80 00 1E 01 F4"),
+            (
+                0x8C,
+                "41 8C 81 80 00 00 00 00 00 00 00 00 00 00 00 00 00 40 00",
+            ),
+        ]);
+
+        // 32-bit values
+        let run_time = device.get_aecd_run_time_1_5().unwrap()[0];
+        assert_eq!(run_time.aecd_1, Some(3600));
+        assert_eq!(run_time.aecd_2, None);
+        assert_eq!(run_time.aecd_3, Some(120));
+        assert_eq!(run_time.aecd_5, None);
+
+        // values of different sizes and scalings
+        let reagent = device.get_nox_reagent_system().unwrap()[0];
+        assert_close(reagent.average_consumption.unwrap(), 1.);
+        assert_eq!(reagent.average_demanded_consumption, None);
+        assert_close(reagent.tank_level.unwrap(), 100.);
+        assert_eq!(reagent.warning_time, Some(7200));
+
+        // pairs of 32-bit values
+        let timers = device.get_aecd_timers_11_15().unwrap()[0];
+        assert_eq!(timers.aecd_1, None);
+        assert_eq!(timers.aecd_2, Some([10, 20]));
+        assert_eq!(timers.aecd_3, None);
+
+        let aftertreatment = device.get_diesel_aftertreatment().unwrap()[0];
+        assert_eq!(aftertreatment.regenerating, Some(true));
+        assert_close(aftertreatment.regeneration_trigger.unwrap(), 50.196);
+        assert_eq!(aftertreatment.average_time_between_regenerations, Some(30));
+        assert_eq!(aftertreatment.average_distance_between_regenerations, None);
+
+        // a support bit for each of eight sensors
+        let oxygen = device.get_wide_range_oxygen_sensors().unwrap()[0];
+        assert_close(oxygen.sensor_1.unwrap(), 1.);
+        assert_eq!(oxygen.sensor_2, None);
+        assert_eq!(oxygen.sensor_7, None);
+        assert_close(oxygen.sensor_8.unwrap(), 0.5);
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn pids_9a_to_a9() {
+        let mut device = device(&[
+            (0x9A, "41 9A 06 01 50 00 FF 6A"),
+            (0xA3, "41 A3 03 FF 38 FC 18 00 04 00 05"),
+            (0xA4, "41 A4 02 30 0D AC"),
+            (0xA4, "41 A4 01 30 0D AC"),
+            (0xA9, "41 A9 01 01 00 00"),
+        ]);
+
+        // signed values
+        let battery = device.get_hybrid_battery_system().unwrap()[0];
+        assert_eq!(battery.charging_state, None);
+        assert_close(battery.voltage.unwrap(), 320.);
+        assert_close(battery.current.unwrap(), -15.);
+
+        let evap = device.get_evap_system_vapor_pressure_sensors().unwrap()[0];
+        assert_close(evap.sensor_a.unwrap(), -50.);
+        assert_eq!(evap.sensor_a_wide, Some(-1000));
+        assert_eq!(evap.sensor_b, None);
+        assert_eq!(evap.sensor_b_wide, None);
+
+        // two values behind one support bit
+        let gear = device.get_transmission_gear().unwrap()[0];
+        assert_eq!(gear.gear, Some(3));
+        assert_close(gear.ratio.unwrap(), 3.5);
+        let gear = device.get_transmission_gear().unwrap()[0];
+        assert_eq!(gear.gear, None);
+        assert_eq!(gear.ratio, None);
+
+        let switch = device.get_abs_disable_switch().unwrap()[0];
+        assert_eq!(switch.active, Some(true));
+        assert!(device.device().verify().is_ok());
+    }
+}
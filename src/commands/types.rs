@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 /// DTC (diagnostic trouble code) metadata
 #[derive(Debug)]
@@ -11,6 +11,10 @@ pub struct DtcsInfo {
     pub dtc_count: u8,
 
     /// Bit field showing availability of seven common tests; the upper bit is currently unused.
+    #[deprecated(
+        since = "0.2.0",
+        note = "the bits are packed from availability and completeness; use `readiness`"
+    )]
     pub common_test_availability: u8,
 
     /// Whether the engine is Diesel
@@ -18,11 +22,205 @@ pub struct DtcsInfo {
 
     /// Bit field showing availability of sixteen engine-specific tests. What the tests are is
     /// based on the value of `is_compression_engine`.
+    #[deprecated(
+        since = "0.2.0",
+        note = "the low byte is availability and the high byte completeness; use `readiness`"
+    )]
     pub specific_test_availability: u16,
+
+    /// The decoded availability and completeness of every readiness monitor
+    pub readiness: Readiness,
+}
+
+/// The status of the readiness monitors, from PID `0x01` (since DTCs were cleared) or `0x41`
+/// (this drive cycle)
+///
+/// For PID `0x41`, a monitor is "supported" if it is enabled in this drive cycle.
+///
+/// # Example
+/// ```
+/// use obd2::{
+///     commands::{Obd2DataRetrieval, ReadinessMonitor},
+///     device::Mock,
+///     Obd2,
+/// };
+///
+/// let mut mock = Mock::new();
+/// mock.expect(&[0x01, 0x00], "41 00 80 00 00 00")
+///     .expect(&[0x01, 0x01], "41 01 81 07 65 04");
+///
+/// let mut device = Obd2::new(mock);
+/// let info = device.get_dtc_info().unwrap().remove(0);
+/// assert!(info.malfunction_indicator_light);
+/// assert!(!info.readiness.is_compression_engine);
+/// assert_eq!(info.readiness.incomplete().collect::<Vec<_>>(), [ReadinessMonitor::Evap]);
+/// assert!(info.readiness.is_ready(1));
+/// assert!(!info.readiness.is_ready(0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Readiness {
+    /// Whether the engine is Diesel, which decides the engine-specific monitors
+    pub is_compression_engine: bool,
+
+    /// The state of each monitor: first the common ones, then the engine-specific ones
+    pub monitors: Vec<MonitorReadiness>,
+}
+
+impl Readiness {
+    /// Decode bytes B to D of PID `0x01` or `0x41`
+    ///
+    /// Byte B holds the common monitors, byte C says which engine-specific monitors are
+    /// supported, and byte D which of them are not complete.
+    pub fn from_bytes(bytes: [u8; 3]) -> Self {
+        let [common, supported, incomplete] = bytes;
+        let is_compression_engine = common & 0x08 != 0;
+        let specific = if is_compression_engine {
+            &COMPRESSION_READINESS_MONITORS
+        } else {
+            &SPARK_READINESS_MONITORS
+        };
+
+        let common = COMMON_READINESS_MONITORS
+            .iter()
+            .enumerate()
+            .map(|(bit, monitor)| (Some(*monitor), common >> bit, common >> (bit + 4)));
+        let specific = specific
+            .iter()
+            .enumerate()
+            .map(|(bit, monitor)| (*monitor, supported >> bit, incomplete >> bit));
+        Readiness {
+            is_compression_engine,
+            monitors: common
+                .chain(specific)
+                .filter_map(|(monitor, supported, incomplete)| {
+                    Some(MonitorReadiness {
+                        monitor: monitor?,
+                        supported: supported & 1 != 0,
+                        complete: incomplete & 1 == 0,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// The monitors that are supported but not complete
+    pub fn incomplete(&self) -> impl Iterator<Item = ReadinessMonitor> + '_ {
+        self.monitors
+            .iter()
+            .filter(|m| m.supported && !m.complete)
+            .map(|m| m.monitor)
+    }
+
+    /// Whether the vehicle would pass an inspection readiness check
+    ///
+    /// Inspection programs allow a few supported monitors to be incomplete, typically one for
+    /// vehicles from model year 2001 and two for older ones. The check fails if more than
+    /// `allowed_incomplete` monitors are incomplete.
+    pub fn is_ready(&self, allowed_incomplete: usize) -> bool {
+        self.incomplete().count() <= allowed_incomplete
+    }
+}
+
+/// The monitors in bits 0 to 2 of byte B, which all engines have
+const COMMON_READINESS_MONITORS: [ReadinessMonitor; 3] = [
+    ReadinessMonitor::Misfire,
+    ReadinessMonitor::FuelSystem,
+    ReadinessMonitor::Components,
+];
+
+/// The monitors of spark ignition engines, by bit of bytes C and D
+const SPARK_READINESS_MONITORS: [Option<ReadinessMonitor>; 8] = [
+    Some(ReadinessMonitor::Catalyst),
+    Some(ReadinessMonitor::HeatedCatalyst),
+    Some(ReadinessMonitor::Evap),
+    Some(ReadinessMonitor::SecondaryAir),
+    Some(ReadinessMonitor::AcRefrigerant),
+    Some(ReadinessMonitor::OxygenSensor),
+    Some(ReadinessMonitor::OxygenSensorHeater),
+    Some(ReadinessMonitor::EgrVvt),
+];
+
+/// The monitors of compression ignition engines, by bit of bytes C and D; bits 2 and 4 are
+/// reserved
+const COMPRESSION_READINESS_MONITORS: [Option<ReadinessMonitor>; 8] = [
+    Some(ReadinessMonitor::NmhcCatalyst),
+    Some(ReadinessMonitor::NoxScr),
+    None,
+    Some(ReadinessMonitor::BoostPressure),
+    None,
+    Some(ReadinessMonitor::ExhaustGasSensor),
+    Some(ReadinessMonitor::ParticulateFilter),
+    Some(ReadinessMonitor::EgrVvt),
+];
+
+/// The state of one monitor in [Readiness]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorReadiness {
+    /// Which monitor the state is for
+    pub monitor: ReadinessMonitor,
+
+    /// Whether the vehicle has the monitor
+    pub supported: bool,
+
+    /// Whether the monitor has run to completion; monitors that are not supported are complete
+    pub complete: bool,
+}
+
+/// A readiness monitor reported in [Readiness]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ReadinessMonitor {
+    /// Misfire
+    Misfire,
+    /// Fuel system
+    FuelSystem,
+    /// Comprehensive components
+    Components,
+    /// Catalyst
+    Catalyst,
+    /// Heated catalyst
+    HeatedCatalyst,
+    /// EVAP (evaporative emission control system)
+    Evap,
+    /// Secondary air injection
+    SecondaryAir,
+    /// A/C refrigerant, which is no longer used
+    AcRefrigerant,
+    /// Oxygen sensors
+    OxygenSensor,
+    /// Oxygen sensor heaters
+    OxygenSensorHeater,
+    /// EGR (exhaust gas recirculation) and/or VVT (variable valve timing)
+    EgrVvt,
+    /// NMHC (non-methane hydrocarbon) catalyst
+    NmhcCatalyst,
+    /// NOx aftertreatment, including SCR (selective catalytic reduction)
+    NoxScr,
+    /// Boost pressure
+    BoostPressure,
+    /// Exhaust gas sensors
+    ExhaustGasSensor,
+    /// PM (particulate matter) filter
+    ParticulateFilter,
 }
 
 /// An individual trouble code from an ECU
-#[derive(Debug)]
+///
+/// Each variant holds the 14-bit number of the code, which is shown as four hex digits after the
+/// category letter (SAE J2012). For example, `P0420` is `Dtc::Powertrain(0x0420)`.
+///
+/// # Example
+/// ```
+/// use obd2::commands::Dtc;
+///
+/// let dtc: Dtc = "U0100".parse().unwrap();
+/// assert_eq!(dtc, Dtc::Network(0x0100));
+/// assert_eq!(u16::from(dtc), 0xC100);
+/// assert_eq!(Dtc::from(0x0420).to_string(), "P0420");
+/// assert!("P4000".parse::<Dtc>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Dtc {
     /// Powertrain, represented with `'P'`
     Powertrain(u16),
@@ -36,7 +234,7 @@ pub enum Dtc {
 
 impl From<u16> for Dtc {
     fn from(val: u16) -> Self {
-        let n = val & 0x3f;
+        let n = val & 0x3fff;
         match val >> 14 {
             0 => Dtc::Powertrain(n),
             1 => Dtc::Chassis(n),
@@ -47,6 +245,18 @@ impl From<u16> for Dtc {
     }
 }
 
+impl From<Dtc> for u16 {
+    fn from(dtc: Dtc) -> Self {
+        let (category, n) = match dtc {
+            Dtc::Powertrain(n) => (0, n),
+            Dtc::Chassis(n) => (1, n),
+            Dtc::Body(n) => (2, n),
+            Dtc::Network(n) => (3, n),
+        };
+        (category << 14) | (n & 0x3fff)
+    }
+}
+
 impl fmt::Display for Dtc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let (c, n) = match self {
@@ -55,7 +265,525 @@ impl fmt::Display for Dtc {
             Self::Body(n) => ('B', n),
             Self::Network(n) => ('U', n),
         };
-        f.write_fmt(format_args!("{}{:03X}", c, n))
+        f.write_fmt(format_args!("{}{:04X}", c, n))
+    }
+}
+
+impl FromStr for Dtc {
+    type Err = ParseDtcError;
+
+    /// Parse a code like `P0420`
+    ///
+    /// The category letter may be lowercase. The first digit must be from `0` to `3`, since it
+    /// only has two bits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDtcError(s.to_owned());
+
+        let mut chars = s.chars();
+        let category: fn(u16) -> Dtc = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('P') => Dtc::Powertrain,
+            Some('C') => Dtc::Chassis,
+            Some('B') => Dtc::Body,
+            Some('U') => Dtc::Network,
+            _ => return Err(err()),
+        };
+
+        let number = chars.as_str();
+        if number.len() != 4 || !number.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+        match u16::from_str_radix(number, 16) {
+            Ok(n) if n <= 0x3fff => Ok(category(n)),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// An error when parsing a [Dtc] from a string
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid DTC `{0}`")]
+pub struct ParseDtcError(String);
+
+/// Which list a [Dtc] in a [DtcReport] came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DtcStatus {
+    /// A confirmed DTC, from service 03
+    Confirmed,
+    /// A pending DTC, from service 07
+    Pending,
+    /// A permanent DTC, from service 0A
+    Permanent,
+}
+
+/// Every DTC stored by one ECU
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DtcReport {
+    /// Confirmed DTCs (service 03)
+    pub confirmed: Vec<Dtc>,
+
+    /// Pending DTCs (service 07)
+    pub pending: Vec<Dtc>,
+
+    /// Permanent DTCs (service 0A)
+    pub permanent: Vec<Dtc>,
+}
+
+impl DtcReport {
+    /// Iterate over every DTC with its status
+    ///
+    /// A DTC can be in more than one list, like a confirmed DTC that is also permanent, so it may
+    /// be returned more than once.
+    pub fn iter(&self) -> impl Iterator<Item = (DtcStatus, Dtc)> + '_ {
+        [
+            (DtcStatus::Confirmed, &self.confirmed),
+            (DtcStatus::Pending, &self.pending),
+            (DtcStatus::Permanent, &self.permanent),
+        ]
+        .into_iter()
+        .flat_map(|(status, dtcs)| dtcs.iter().map(move |d| (status, *d)))
+    }
+
+    /// Whether the ECU has no DTCs at all
+    pub fn is_empty(&self) -> bool {
+        self.confirmed.is_empty() && self.pending.is_empty() && self.permanent.is_empty()
+    }
+}
+
+/// Where an oxygen sensor is in the exhaust
+///
+/// Banks are the groups of cylinders with their own exhaust, and sensor 1 is the closest to the
+/// engine. Depending on whether the vehicle describes its sensors with
+/// [get_oxygen_sensors_2_bank](super::Obd2DataRetrieval::get_oxygen_sensors_2_bank) or
+/// [get_oxygen_sensors_4_bank](super::Obd2DataRetrieval::get_oxygen_sensors_4_bank), the same
+/// sensor number means a different location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OxygenSensorLocation {
+    /// The bank, from 1
+    pub bank: u8,
+
+    /// The sensor in the bank, from 1
+    pub sensor: u8,
+}
+
+impl OxygenSensorLocation {
+    /// Get the location of the sensor with the given number, from 1 to 8
+    ///
+    /// Numbers are assigned bank by bank, with either two or four sensors in each bank.
+    pub(super) fn from_number(number: u8, sensors_per_bank: u8) -> Self {
+        OxygenSensorLocation {
+            bank: (number - 1) / sensors_per_bank + 1,
+            sensor: (number - 1) % sensors_per_bank + 1,
+        }
+    }
+}
+
+impl fmt::Display for OxygenSensorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bank {} sensor {}", self.bank, self.sensor)
+    }
+}
+
+/// The result of one oxygen sensor monitoring test (service 05)
+///
+/// Service 05 is how vehicles that do not use CAN report these tests; on CAN, they are part of the
+/// [on-board monitor tests](MonitorTestResult). Standard tests are converted to volts or seconds,
+/// while manufacturer specific tests keep the raw value.
+///
+/// # Example
+/// ```
+/// use obd2::{
+///     commands::{Obd2DataRetrieval, OxygenSensorLocation},
+///     device::{HeaderFormat, Mock},
+///     Obd2,
+/// };
+///
+/// let mut mock = Mock::new();
+/// mock.headers(HeaderFormat::Legacy)
+///     .expect(&[0x01, 0x00], "48 6B 10 41 00 00 00 20 00 00")
+///     .expect(&[0x01, 0x13], "48 6B 10 41 13 03 00")
+///     .expect(&[0x05, 0x00, 0x00], "48 6B 10 45 00 00 60 00 00 00 00")
+///     .expect(&[0x05, 0x02, 0x01], "48 6B 10 45 02 01 5A 00")
+///     .expect(&[0x05, 0x03, 0x01], "48 6B 10 45 03 01 50 00")
+///     .expect(&[0x05, 0x02, 0x02], "48 6B 10 45 02 02 5C 00")
+///     .expect(&[0x05, 0x03, 0x02], "48 6B 10 45 03 02 4C 00");
+///
+/// let mut device = Obd2::new(mock);
+/// let results = device.get_oxygen_sensor_test_results().unwrap().pop_first().unwrap().1;
+/// let downstream = &results[&OxygenSensorLocation { bank: 1, sensor: 2 }];
+/// assert_eq!(downstream[0].name(), Some("Lean to rich sensor threshold voltage"));
+/// assert!((downstream[0].value - 0.46).abs() < 1e-6);
+/// assert_eq!(downstream[0].unit, "V");
+/// assert!(device.device().verify().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct OxygenSensorTestResult {
+    /// The ID of the test. IDs from `0x21` are manufacturer specific.
+    pub tid: u8,
+
+    /// The number of the oxygen sensor, from 1 to 8
+    pub sensor_id: u8,
+
+    /// The value measured by the test
+    pub value: f32,
+
+    /// The lowest value that passes the test, if the ECU sent limits
+    pub min: Option<f32>,
+
+    /// The highest value that passes the test, if the ECU sent limits
+    pub max: Option<f32>,
+
+    /// The unit of the value and limits, or `""` if the test is unknown
+    pub unit: &'static str,
+}
+
+impl OxygenSensorTestResult {
+    /// Decode the data of the response to a test: the value, optionally followed by the minimum
+    /// and maximum
+    pub(crate) fn from_data(tid: u8, sensor_id: u8, data: &[u8]) -> Option<Self> {
+        let (scale, unit) = match super::monitoring::oxygen_sensor_test(tid) {
+            Some((_, scale, unit)) => (scale, unit),
+            None => (1., ""),
+        };
+        let scaled = |v: u8| f32::from(v) * scale;
+
+        let (value, limits) = match *data {
+            [value] => (value, None),
+            [value, min, max] => (value, Some((scaled(min), scaled(max)))),
+            _ => return None,
+        };
+        Some(OxygenSensorTestResult {
+            tid,
+            sensor_id,
+            value: scaled(value),
+            min: limits.map(|l| l.0),
+            max: limits.map(|l| l.1),
+            unit,
+        })
+    }
+
+    /// The name of the test, if its TID is a standard one
+    pub fn name(&self) -> Option<&'static str> {
+        super::monitoring::oxygen_sensor_test(self.tid).map(|t| t.0)
+    }
+
+    /// Whether the value is within the limits, or `None` if the ECU sent no limits
+    pub fn passed(&self) -> Option<bool> {
+        Some(self.min? <= self.value && self.value <= self.max?)
+    }
+}
+
+/// The result of one test of an on-board monitor (service 06)
+///
+/// Each monitor, identified by its OBDMID, can run several tests, identified by their TID. The
+/// value and limits are converted with the Unit and Scaling ID the ECU sent with them (SAE J1979
+/// appendix E); if the ID is unknown, they are the raw values.
+///
+/// # Example
+/// ```
+/// use obd2::{commands::Obd2DataRetrieval, device::Mock, Obd2};
+///
+/// let mut mock = Mock::new();
+/// mock.expect(&[0x06, 0x00], "46 00 80 00 00 01")
+///     .expect(&[0x06, 0x20], "46 20 80 00 00 00")
+///     .expect(&[0x06, 0x01], "46 01 01 0A 0D 48 00 00 1F 40 01 05 10 00 64 00 00 00 C8")
+///     .expect(&[0x06, 0x21], "46 21 80 01 00 32 00 64 FF FF");
+///
+/// let mut device = Obd2::new(mock);
+/// let results = device.get_monitor_test_results().unwrap().pop_first().unwrap().1;
+/// assert_eq!(results.len(), 3);
+/// assert_eq!(results[0].name().unwrap(), "Oxygen sensor monitor bank 1 sensor 1");
+/// assert!((results[0].value - 414.8).abs() < 1e-9);
+/// assert_eq!(results[0].unit, "mV");
+/// assert!(results[0].passed);
+/// assert_eq!((results[1].tid, results[1].max), (0x05, 200.0));
+/// assert_eq!(results[2].name().unwrap(), "Catalyst monitor bank 1");
+/// assert!(!results[2].passed);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MonitorTestResult {
+    /// The OBDMID of the monitor
+    pub mid: u8,
+
+    /// The ID of the test. IDs below `0x80` are standardized, the rest are manufacturer specific.
+    pub tid: u8,
+
+    /// The Unit and Scaling ID used for the value and limits
+    pub unit_and_scaling: u8,
+
+    /// The value measured by the test
+    pub value: f64,
+
+    /// The lowest value that passes the test
+    pub min: f64,
+
+    /// The highest value that passes the test
+    pub max: f64,
+
+    /// The unit of the value and limits, or `""` if it has none or is unknown
+    pub unit: &'static str,
+
+    /// Whether the value is within the limits
+    pub passed: bool,
+}
+
+impl MonitorTestResult {
+    /// Decode one test result record: OBDMID, TID, Unit and Scaling ID, then the value, minimum,
+    /// and maximum as 16-bit numbers
+    pub(super) fn from_record(record: &[u8; 9]) -> Self {
+        let scaling = super::monitoring::unit_and_scaling(record[2]);
+        let raw = |i: usize| {
+            let raw = u16::from_be_bytes([record[i], record[i + 1]]);
+            if scaling.signed {
+                f64::from(raw as i16)
+            } else {
+                f64::from(raw)
+            }
+        };
+        let physical = |raw: f64| raw * scaling.scale + scaling.offset;
+        let (value, min, max) = (raw(3), raw(5), raw(7));
+
+        MonitorTestResult {
+            mid: record[0],
+            tid: record[1],
+            unit_and_scaling: record[2],
+            value: physical(value),
+            min: physical(min),
+            max: physical(max),
+            unit: scaling.unit,
+            passed: min <= value && value <= max,
+        }
+    }
+
+    /// The name of the monitor, if its OBDMID is a standard one
+    pub fn name(&self) -> Option<String> {
+        super::monitoring::monitor_name(self.mid)
+    }
+}
+
+/// In-use monitor performance tracking (service 09, InfoType 08 or 0B)
+///
+/// For each monitor, the ECU counts how often the monitor ran to completion (the numerator) and
+/// how often the vehicle was driven in a way that would allow it to run (the denominator).
+/// Regulations set the lowest ratio that a monitor may have.
+///
+/// # Example
+/// ```
+/// use obd2::{
+///     commands::{Obd2DataRetrieval, PerformanceMonitor},
+///     device::Mock,
+///     Obd2,
+/// };
+///
+/// let mut mock = Mock::new();
+/// mock.expect(
+///     &[0x09, 0x08],
+///     "49 08 10 00 64 00 C8 00 32 00 64 00 00 00 00 00 0A 00 0A 00 00 00 00 \
+///      00 00 00 00 00 00 00 00 00 05 00 64",
+/// );
+///
+/// let mut device = Obd2::new(mock);
+/// let tracking = device.get_spark_performance_tracking().unwrap().remove(0);
+/// assert_eq!(tracking.ignition_cycles, 200);
+/// assert_eq!(tracking.monitors[0].monitor, PerformanceMonitor::CatalystBank1);
+/// assert_eq!(tracking.monitors[0].ratio(), Some(0.5));
+/// assert_eq!(tracking.monitors[1].ratio(), None);
+/// assert_eq!(tracking.monitors[6].monitor, PerformanceMonitor::Evap);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InUsePerformanceTracking {
+    /// How many times the vehicle was driven in the conditions required for all monitors
+    pub obd_conditions: u16,
+
+    /// The number of ignition cycles
+    pub ignition_cycles: u16,
+
+    /// The counters of each monitor, in the order they were sent
+    pub monitors: Vec<MonitorRatio>,
+}
+
+impl InUsePerformanceTracking {
+    /// Decode the counters sent for InfoType 08 (spark ignition) or 0B (compression ignition)
+    ///
+    /// Older ECUs send fewer counters, so the monitors at the end of the list may be missing.
+    /// Newer ECUs may send counters for monitors added by later revisions of SAE J1979, which are
+    /// kept as [PerformanceMonitor::Unknown].
+    pub(super) fn from_counters(info_type: u8, counters: &[u16]) -> Option<Self> {
+        let monitors: &[PerformanceMonitor] = match info_type {
+            0x08 => &SPARK_MONITORS,
+            0x0B => &COMPRESSION_MONITORS,
+            _ => return None,
+        };
+        let (&[obd_conditions, ignition_cycles], ratios) = counters.split_first_chunk::<2>()?;
+        if ratios.len() % 2 != 0 {
+            return None;
+        }
+
+        let unknown = (monitors.len()..).map(|i| PerformanceMonitor::Unknown(i as u8));
+        Some(InUsePerformanceTracking {
+            obd_conditions,
+            ignition_cycles,
+            monitors: monitors
+                .iter()
+                .copied()
+                .chain(unknown)
+                .zip(ratios.chunks_exact(2))
+                .map(|(monitor, ratio)| MonitorRatio {
+                    monitor,
+                    numerator: ratio[0],
+                    denominator: ratio[1],
+                })
+                .collect(),
+        })
+    }
+}
+
+/// The monitors of spark ignition engines, in the order of InfoType 08
+const SPARK_MONITORS: [PerformanceMonitor; 9] = [
+    PerformanceMonitor::CatalystBank1,
+    PerformanceMonitor::CatalystBank2,
+    PerformanceMonitor::OxygenSensorBank1,
+    PerformanceMonitor::OxygenSensorBank2,
+    PerformanceMonitor::EgrVvt,
+    PerformanceMonitor::SecondaryAir,
+    PerformanceMonitor::Evap,
+    PerformanceMonitor::SecondaryOxygenSensorBank1,
+    PerformanceMonitor::SecondaryOxygenSensorBank2,
+];
+
+/// The monitors of compression ignition engines, in the order of InfoType 0B
+const COMPRESSION_MONITORS: [PerformanceMonitor; 8] = [
+    PerformanceMonitor::NmhcCatalyst,
+    PerformanceMonitor::NoxCatalyst,
+    PerformanceMonitor::NoxAdsorber,
+    PerformanceMonitor::ParticulateFilter,
+    PerformanceMonitor::ExhaustGasSensor,
+    PerformanceMonitor::EgrVvt,
+    PerformanceMonitor::BoostPressure,
+    PerformanceMonitor::FuelSystem,
+];
+
+/// The counters of one monitor in [InUsePerformanceTracking]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorRatio {
+    /// Which monitor the counters are for
+    pub monitor: PerformanceMonitor,
+
+    /// How many times the monitor ran to completion
+    pub numerator: u16,
+
+    /// How many times the vehicle was driven in the conditions required for the monitor
+    pub denominator: u16,
+}
+
+impl MonitorRatio {
+    /// The ratio of the numerator to the denominator, or `None` if the denominator is zero
+    pub fn ratio(&self) -> Option<f32> {
+        (self.denominator != 0).then(|| f32::from(self.numerator) / f32::from(self.denominator))
+    }
+}
+
+/// A monitor whose performance is tracked in [InUsePerformanceTracking]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum PerformanceMonitor {
+    /// Catalyst, bank 1
+    CatalystBank1,
+    /// Catalyst, bank 2
+    CatalystBank2,
+    /// Oxygen sensors, bank 1
+    OxygenSensorBank1,
+    /// Oxygen sensors, bank 2
+    OxygenSensorBank2,
+    /// EGR (exhaust gas recirculation) and/or VVT (variable valve timing)
+    EgrVvt,
+    /// Secondary air injection
+    SecondaryAir,
+    /// EVAP (evaporative emission control system)
+    Evap,
+    /// Secondary oxygen sensors, bank 1
+    SecondaryOxygenSensorBank1,
+    /// Secondary oxygen sensors, bank 2
+    SecondaryOxygenSensorBank2,
+    /// NMHC (non-methane hydrocarbon) catalyst
+    NmhcCatalyst,
+    /// NOx catalyst, including SCR (selective catalytic reduction)
+    NoxCatalyst,
+    /// NOx adsorber
+    NoxAdsorber,
+    /// PM (particulate matter) filter
+    ParticulateFilter,
+    /// Exhaust gas sensors
+    ExhaustGasSensor,
+    /// Boost pressure
+    BoostPressure,
+    /// Fuel system
+    FuelSystem,
+    /// A monitor that is not known yet, with the position of its counters among the monitors of
+    /// the InfoType, counting from 0
+    Unknown(u8),
+}
+
+/// The service 01 PIDs supported by an ECU
+///
+/// Each ECU lists the PIDs it supports in bitmaps at PID `0x00`, `0x20`, and so on up to `0xE0`,
+/// where the last bit of each bitmap says whether the next one is supported.
+///
+/// # Example
+/// ```
+/// use obd2::{commands::Obd2DataRetrieval, device::Mock, EcuAddress, Error, Obd2};
+///
+/// let mut mock = Mock::new();
+/// mock.expect(&[0x01, 0x00], "41 00 80 18 00 01")
+///     .expect(&[0x01, 0x20], "41 20 00 02 00 00")
+///     .expect(&[0x01, 0x0D], "41 0D 32");
+///
+/// let mut device = Obd2::new(mock);
+/// let supported = &device.supported_pids().unwrap()[&EcuAddress::Unknown(0)];
+/// assert!(supported.contains(0x0D) && supported.contains(0x2F));
+/// assert_eq!(supported.iter().collect::<Vec<_>>(), [0x01, 0x0C, 0x0D, 0x20, 0x2F]);
+///
+/// // the speed is supported, but the coolant temperature is not even requested
+/// assert_eq!(device.get_speed().unwrap(), [50]);
+/// assert!(matches!(
+///     device.get_engine_coolant_temperature(),
+///     Err(Error::Unsupported(0x01, 0x05))
+/// ));
+/// assert!(device.device().verify().is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SupportedPids {
+    /// The bitmaps at PID `0x00`, `0x20`, ..., `0xE0`
+    bitmaps: [u32; 8],
+}
+
+impl SupportedPids {
+    /// Whether the PID is supported
+    ///
+    /// PID `0x00` is always supported.
+    pub fn contains(&self, pid: u8) -> bool {
+        match pid.checked_sub(1) {
+            Some(i) => self.bitmaps[usize::from(i / 0x20)] & (1 << (0x1F - i % 0x20)) != 0,
+            None => true,
+        }
+    }
+
+    /// Iterate over the supported PIDs in ascending order, leaving out PID `0x00`
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0x01..=0xFF).filter(|pid| self.contains(*pid))
+    }
+}
+
+impl FromIterator<u8> for SupportedPids {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut supported = SupportedPids::default();
+        for i in iter.into_iter().filter_map(|pid| pid.checked_sub(1)) {
+            supported.bitmaps[usize::from(i / 0x20)] |= 1 << (0x1F - i % 0x20);
+        }
+        supported
     }
 }
 
@@ -71,7 +799,318 @@ pub struct OxygenSensorData {
     pub shrft: f32,
 }
 
+/// Data retrieved when reading a wide range oxygen sensor that reports its voltage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WideRangeOxygenSensorVoltage {
+    /// The equivalence ratio (λ)
+    ///
+    /// This is the actual air/fuel ratio divided by the stoichiometric ratio, so values above 1
+    /// are lean. It ranges from 0 to just under 2.
+    pub equivalence_ratio: f32,
+
+    /// The voltage (V), from 0 to just under 8
+    pub voltage: f32,
+}
+
+/// Data retrieved when reading a wide range oxygen sensor that reports its current
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WideRangeOxygenSensorCurrent {
+    /// The equivalence ratio (λ)
+    ///
+    /// This is the actual air/fuel ratio divided by the stoichiometric ratio, so values above 1
+    /// are lean. It ranges from 0 to just under 2.
+    pub equivalence_ratio: f32,
+
+    /// The current (mA), from -128 to just under 128
+    pub current: f32,
+}
+
+/// The engine torque at idle and at four points of the torque curve, from PID `0x64`
+///
+/// Each value is in %, relative to the [engine reference
+/// torque](super::Obd2DataRetrieval::get_engine_reference_torque).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnginePercentTorque {
+    /// Torque at idle
+    pub idle: i16,
+
+    /// Torque at the four points of the torque curve
+    pub points: [i16; 4],
+}
+
+/// Where the engine is relative to an NTE (not-to-exceed) control area, from PID `0x7D` (NOx) or
+/// `0x7E` (PM)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NteStatus {
+    /// Whether the engine is inside the control area
+    pub inside_control_area: bool,
+
+    /// Whether the engine is outside the control area
+    pub outside_control_area: bool,
+
+    /// Whether the engine is inside the manufacturer-specific carve-out area
+    pub inside_carve_out_area: bool,
+
+    /// Whether an NTE deficiency is active
+    pub deficiency: bool,
+}
+
+impl From<u8> for NteStatus {
+    fn from(value: u8) -> Self {
+        NteStatus {
+            inside_control_area: value & 0x01 != 0,
+            outside_control_area: value & 0x02 != 0,
+            inside_carve_out_area: value & 0x04 != 0,
+            deficiency: value & 0x08 != 0,
+        }
+    }
+}
+
+/// The state and history of the SCR (selective catalytic reduction) inducement system, from PID
+/// `0x88`
+///
+/// The inducement system limits the engine's performance when there is a problem with the NOx
+/// reagent (Diesel exhaust fluid).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrInducementSystem {
+    /// The actual state of the inducement system (bitfield)
+    ///
+    /// The low bits say why inducement is active, like low reagent level, incorrect reagent or a
+    /// deviation of the reagent consumption. Bit 7 says whether the system is active.
+    pub state: u8,
+
+    /// The distances in km recorded by the inducement history, in the order J1979 lists them
+    pub distances: [u16; 6],
+}
+
+/// The Diesel exhaust fluid sensor, from PID `0x9B`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DieselExhaustFluidSensor {
+    /// The type of fluid detected (bitfield)
+    pub fluid_type: u8,
+
+    /// The urea concentration in %
+    pub concentration: f32,
+
+    /// The fluid temperature in ºC
+    pub temperature: i16,
+
+    /// The tank level in %
+    pub level: f32,
+}
+
+/// The engine and vehicle fuel rates in g/s, from PID `0x9D`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuelRates {
+    /// The fuel used by the engine
+    pub engine: f32,
+
+    /// The fuel used by the whole vehicle, for example including an auxiliary heater
+    pub vehicle: f32,
+}
+
+/// Maximum values that can be reported for some PIDs, from PID `0x4F`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaximumValues {
+    /// Maximum equivalence ratio
+    pub equivalence_ratio: u8,
+
+    /// Maximum oxygen sensor voltage (V)
+    pub oxygen_sensor_voltage: u8,
+
+    /// Maximum oxygen sensor current (mA)
+    pub oxygen_sensor_current: u8,
+
+    /// Maximum intake manifold absolute pressure (kPa)
+    pub intake_manifold_pressure: u16,
+}
+
+/// The type of fuel a vehicle uses, from PID `0x51`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FuelType {
+    /// Not available (`0x00`)
+    NotAvailable,
+    /// Gasoline (`0x01`)
+    Gasoline,
+    /// Methanol (`0x02`)
+    Methanol,
+    /// Ethanol (`0x03`)
+    Ethanol,
+    /// Diesel (`0x04`)
+    Diesel,
+    /// Liquefied petroleum gas (`0x05`)
+    Lpg,
+    /// Compressed natural gas (`0x06`)
+    Cng,
+    /// Propane (`0x07`)
+    Propane,
+    /// Battery electric (`0x08`)
+    Electric,
+    /// Bi-fuel vehicle running on gasoline (`0x09`)
+    BifuelGasoline,
+    /// Bi-fuel vehicle running on methanol (`0x0A`)
+    BifuelMethanol,
+    /// Bi-fuel vehicle running on ethanol (`0x0B`)
+    BifuelEthanol,
+    /// Bi-fuel vehicle running on liquefied petroleum gas (`0x0C`)
+    BifuelLpg,
+    /// Bi-fuel vehicle running on compressed natural gas (`0x0D`)
+    BifuelCng,
+    /// Bi-fuel vehicle running on propane (`0x0E`)
+    BifuelPropane,
+    /// Bi-fuel vehicle running on electricity (`0x0F`)
+    BifuelElectric,
+    /// Bi-fuel vehicle running on electricity and a combustion engine (`0x10`)
+    BifuelElectricAndCombustion,
+    /// Hybrid gasoline (`0x11`)
+    HybridGasoline,
+    /// Hybrid ethanol (`0x12`)
+    HybridEthanol,
+    /// Hybrid Diesel (`0x13`)
+    HybridDiesel,
+    /// Hybrid running on electricity (`0x14`)
+    HybridElectric,
+    /// Hybrid running on electricity and a combustion engine (`0x15`)
+    HybridElectricAndCombustion,
+    /// Hybrid in regenerative mode (`0x16`)
+    HybridRegenerative,
+    /// Bi-fuel vehicle running on Diesel (`0x17`)
+    BifuelDiesel,
+    /// A reserved value
+    Other(u8),
+}
+
+impl From<u8> for FuelType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => FuelType::NotAvailable,
+            0x01 => FuelType::Gasoline,
+            0x02 => FuelType::Methanol,
+            0x03 => FuelType::Ethanol,
+            0x04 => FuelType::Diesel,
+            0x05 => FuelType::Lpg,
+            0x06 => FuelType::Cng,
+            0x07 => FuelType::Propane,
+            0x08 => FuelType::Electric,
+            0x09 => FuelType::BifuelGasoline,
+            0x0A => FuelType::BifuelMethanol,
+            0x0B => FuelType::BifuelEthanol,
+            0x0C => FuelType::BifuelLpg,
+            0x0D => FuelType::BifuelCng,
+            0x0E => FuelType::BifuelPropane,
+            0x0F => FuelType::BifuelElectric,
+            0x10 => FuelType::BifuelElectricAndCombustion,
+            0x11 => FuelType::HybridGasoline,
+            0x12 => FuelType::HybridEthanol,
+            0x13 => FuelType::HybridDiesel,
+            0x14 => FuelType::HybridElectric,
+            0x15 => FuelType::HybridElectricAndCombustion,
+            0x16 => FuelType::HybridRegenerative,
+            0x17 => FuelType::BifuelDiesel,
+            other => FuelType::Other(other),
+        }
+    }
+}
+
+impl From<FuelType> for u8 {
+    fn from(fuel_type: FuelType) -> Self {
+        match fuel_type {
+            FuelType::NotAvailable => 0x00,
+            FuelType::Gasoline => 0x01,
+            FuelType::Methanol => 0x02,
+            FuelType::Ethanol => 0x03,
+            FuelType::Diesel => 0x04,
+            FuelType::Lpg => 0x05,
+            FuelType::Cng => 0x06,
+            FuelType::Propane => 0x07,
+            FuelType::Electric => 0x08,
+            FuelType::BifuelGasoline => 0x09,
+            FuelType::BifuelMethanol => 0x0A,
+            FuelType::BifuelEthanol => 0x0B,
+            FuelType::BifuelLpg => 0x0C,
+            FuelType::BifuelCng => 0x0D,
+            FuelType::BifuelPropane => 0x0E,
+            FuelType::BifuelElectric => 0x0F,
+            FuelType::BifuelElectricAndCombustion => 0x10,
+            FuelType::HybridGasoline => 0x11,
+            FuelType::HybridEthanol => 0x12,
+            FuelType::HybridDiesel => 0x13,
+            FuelType::HybridElectric => 0x14,
+            FuelType::HybridElectricAndCombustion => 0x15,
+            FuelType::HybridRegenerative => 0x16,
+            FuelType::BifuelDiesel => 0x17,
+            FuelType::Other(other) => other,
+        }
+    }
+}
+
 pub(super) mod private {
     pub trait Sealed {}
     impl<T: crate::Obd2Device> Sealed for T {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dtc_round_trip() {
+        for (raw, text, dtc) in [
+            (0x0420, "P0420", Dtc::Powertrain(0x0420)),
+            (0x4123, "C0123", Dtc::Chassis(0x0123)),
+            (0x9234, "B1234", Dtc::Body(0x1234)),
+            (0xC100, "U0100", Dtc::Network(0x0100)),
+            (0xFFFF, "U3FFF", Dtc::Network(0x3FFF)),
+        ] {
+            assert_eq!(Dtc::from(raw), dtc);
+            assert_eq!(u16::from(dtc), raw);
+            assert_eq!(dtc.to_string(), text);
+            assert_eq!(text.parse::<Dtc>(), Ok(dtc));
+        }
+
+        assert_eq!("u3fff".parse::<Dtc>(), Ok(Dtc::Network(0x3FFF)));
+        assert!("U4000".parse::<Dtc>().is_err());
+        assert!("P042".parse::<Dtc>().is_err());
+        assert!("X0420".parse::<Dtc>().is_err());
+    }
+
+    #[test]
+    fn performance_tracking_extra_monitors() {
+        let mut counters = vec![100, 200];
+        counters.extend((1..=COMPRESSION_MONITORS.len() as u16 + 1).flat_map(|n| [n, 10]));
+
+        let tracking = InUsePerformanceTracking::from_counters(0x0B, &counters).unwrap();
+        assert_eq!(tracking.obd_conditions, 100);
+        assert_eq!(tracking.ignition_cycles, 200);
+        assert_eq!(tracking.monitors.len(), 9);
+        assert_eq!(
+            tracking.monitors[7],
+            MonitorRatio {
+                monitor: PerformanceMonitor::FuelSystem,
+                numerator: 8,
+                denominator: 10,
+            }
+        );
+        assert_eq!(
+            tracking.monitors[8],
+            MonitorRatio {
+                monitor: PerformanceMonitor::Unknown(8),
+                numerator: 9,
+                denominator: 10,
+            }
+        );
+
+        // fewer monitors are still accepted, but not half a pair
+        let tracking = InUsePerformanceTracking::from_counters(0x08, &[100, 200, 1, 2]).unwrap();
+        assert_eq!(
+            tracking.monitors[0].monitor,
+            PerformanceMonitor::CatalystBank1
+        );
+        assert_eq!(
+            InUsePerformanceTracking::from_counters(0x08, &[100, 200, 1]),
+            None
+        );
+    }
+}
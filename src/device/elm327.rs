@@ -2,14 +2,25 @@ use log::{debug, info, trace};
 use std::{
     collections::VecDeque,
     io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
     thread, time,
 };
 
-use super::{Error, Obd2BaseDevice, Obd2Reader, Result};
+use super::{Error, HeaderFormat, Obd2BaseDevice, Obd2Reader, Result, Transport};
+
+/// The baud rate ELM327 devices use after a power-on reset
+const DEFAULT_BAUD_RATE: u32 = 38400;
+
+/// How long a single read from a serial port may block when no data is available
+#[cfg(feature = "serialport")]
+const SERIAL_READ_TIMEOUT: time::Duration = time::Duration::from_millis(10);
 
 /// An ELM327 OBD-II adapter
 ///
-/// It communicates with the computer over UART using an FTDI FT232R USB-to-UART converter.
+/// It communicates with the computer over UART, usually through a USB-to-UART converter. An FTDI
+/// FT232R can be opened directly with [Self::new], any other serial port can be opened with
+/// [Self::from_serial_port], Wi-Fi adapters can be reached with [Self::from_tcp], and other byte
+/// streams can be used with [Self::from_transport].
 /// Commands to the device itself are indicated by sending "AT" followed by the command, while
 /// plain strings of hex data indicate OBD-II requests to be sent to the vehicle. The responses of
 /// the vehicle are echoed back as hex characters. Capitalization and spaces are always ignored.
@@ -17,11 +28,13 @@ use super::{Error, Obd2BaseDevice, Obd2Reader, Result};
 /// [Datasheet for v1.4b](https://github.com/rsammelson/obd2/blob/master/docs/ELM327DSH.pdf), and
 /// the [source](https://www.elmelectronics.com/products/dsheets/).
 pub struct Elm327 {
-    device: ftdi::Device,
+    device: Box<dyn Transport>,
     buffer: VecDeque<u8>,
     baud_rate: u32,
+    header_format: Option<HeaderFormat>,
 }
 
+#[cfg(feature = "ftdi")]
 impl Default for Elm327 {
     /// Create a Elm327 device
     ///
@@ -33,7 +46,55 @@ impl Default for Elm327 {
 }
 
 impl Obd2BaseDevice for Elm327 {
+    /// Send an OBD-II command and get the reply
+    ///
+    /// Status messages from the ELM327 are checked: informational messages like `SEARCHING...`
+    /// are removed, and messages like `NO DATA` or `CAN ERROR` are turned into the matching
+    /// [Error].
+    fn cmd(&mut self, cmd: &[u8]) -> Result<Option<String>> {
+        self.send_cmd(cmd)?;
+        self.get_response()?
+            .and_then(|resp| String::from_utf8(resp).ok())
+            .map(check_status)
+            .transpose()
+    }
+
+    /// Get the format of the headers at the start of each line of a reply
+    ///
+    /// Headers are always enabled, so this depends on the protocol the ELM327 is using. The
+    /// protocol is requested from the device the first time this is called after a reset.
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        if let Some(format) = self.header_format {
+            return Ok(format);
+        }
+
+        let protocol = self.serial_cmd("ATDPN")?.unwrap_or_default();
+        debug!("header_format: got protocol {:?}", protocol);
+
+        // an "A" prefix means the protocol was found automatically; a lone "A" is protocol 10
+        let protocol = protocol.trim();
+        let number = match protocol.strip_prefix('A') {
+            Some(number) if protocol.len() == 2 => number,
+            _ => protocol,
+        };
+        let format = match number {
+            "1" | "2" | "3" | "4" | "5" => HeaderFormat::Legacy,
+            "6" | "8" | "B" | "C" => HeaderFormat::Can11,
+            "7" | "9" | "A" => HeaderFormat::Can29,
+            _ => {
+                return Err(Error::Communication(format!(
+                    "header_format: unknown protocol {:?}",
+                    protocol
+                )))
+            }
+        };
+
+        self.header_format = Some(format);
+        Ok(format)
+    }
+
     fn reset(&mut self) -> Result<()> {
+        self.header_format = None;
         self.flush_buffers()?;
         self.reset_ic()?;
         thread::sleep(time::Duration::from_millis(500));
@@ -69,21 +130,89 @@ impl Obd2Reader for Elm327 {
 }
 
 impl Elm327 {
-    fn new() -> Result<Self> {
+    /// Connect to an ELM327 behind an FTDI FT232R USB-to-UART converter
+    ///
+    /// The first FT232R found with the default vendor and product IDs is used.
+    #[cfg(feature = "ftdi")]
+    pub fn new() -> Result<Self> {
         let mut ftdi_device = ftdi::find_by_vid_pid(0x0403, 0x6001)
             .interface(ftdi::Interface::A)
             .open()?;
 
-        ftdi_device.set_baud_rate(38400)?;
+        ftdi_device.set_baud_rate(DEFAULT_BAUD_RATE)?;
         ftdi_device.configure(ftdi::Bits::Eight, ftdi::StopBits::One, ftdi::Parity::None)?;
         // device.set_latency_timer(2).unwrap();
 
         ftdi_device.usb_reset()?;
 
+        Self::open(Box::new(ftdi_device), DEFAULT_BAUD_RATE)
+    }
+
+    /// Connect to an ELM327 on a serial port
+    ///
+    /// The `path` is the name of the port, like `/dev/ttyUSB0` or `COM3`. This works for any
+    /// USB-to-UART converter with an operating system driver (CH340, PL2303, CDC-ACM, etc.). The
+    /// `baud_rate` must match the rate the device is currently using, which is 38400 for most
+    /// devices after they are powered on.
+    #[cfg(feature = "serialport")]
+    pub fn from_serial_port(path: &str, baud_rate: u32) -> Result<Self> {
+        let port = serialport::new(path, baud_rate)
+            .data_bits(serialport::DataBits::Eight)
+            .stop_bits(serialport::StopBits::One)
+            .parity(serialport::Parity::None)
+            .timeout(SERIAL_READ_TIMEOUT)
+            .open()?;
+
+        Self::open(Box::new(port), baud_rate)
+    }
+
+    /// Connect to an ELM327 over TCP
+    ///
+    /// This is used by Wi-Fi adapters, which usually listen on `192.168.0.10` port `35000`. Each
+    /// address `host` resolves to is tried in turn, waiting up to `connect_timeout` for each. The
+    /// `read_timeout` is how long a single read from the socket waits for data, and must not be
+    /// zero; it should be short, since longer timeouts are handled when reading responses.
+    pub fn from_tcp(
+        host: &str,
+        port: u16,
+        connect_timeout: time::Duration,
+        read_timeout: time::Duration,
+    ) -> Result<Self> {
+        let mut last_error = None;
+        for addr in (host, port).to_socket_addrs()? {
+            debug!("from_tcp: trying {}", addr);
+            match TcpStream::connect_timeout(&addr, connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(read_timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Self::from_transport(stream);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(match last_error {
+            Some(e) => e.into(),
+            None => Error::Communication(format!(
+                "from_tcp: {}:{} did not resolve to any address",
+                host, port
+            )),
+        })
+    }
+
+    /// Connect to an ELM327 over an already configured transport
+    ///
+    /// The transport is assumed to be using the ELM327's default baud rate, if it has one.
+    pub fn from_transport<T: Transport + 'static>(transport: T) -> Result<Self> {
+        Self::open(Box::new(transport), DEFAULT_BAUD_RATE)
+    }
+
+    fn open(transport: Box<dyn Transport>, baud_rate: u32) -> Result<Self> {
         let mut device = Elm327 {
-            device: ftdi_device,
+            device: transport,
             buffer: VecDeque::new(),
-            baud_rate: 38400,
+            baud_rate,
+            header_format: None,
         };
 
         device.connect(false)?;
@@ -102,8 +231,7 @@ impl Elm327 {
     }
 
     fn flush_buffers(&mut self) -> Result<()> {
-        self.device.usb_purge_buffers()?;
-        Ok(())
+        self.device.purge_buffers()
     }
 
     fn connect(&mut self, check_baud_rate: bool) -> Result<()> {
@@ -127,11 +255,11 @@ impl Elm327 {
     fn reset_ic(&mut self) -> Result<()> {
         info!("Performing IC reset");
         self.send_serial_str("ATZ")?;
+        // read the response outside of the log macro, which skips it when logging is disabled
+        let response = self.get_response()?;
         debug!(
             "reset_ic: got response {:?}",
-            self.get_response()?
-                .as_ref()
-                .map(|l| std::str::from_utf8(l.as_slice()))
+            response.as_ref().map(|l| std::str::from_utf8(l.as_slice()))
         );
         Ok(())
     }
@@ -139,17 +267,21 @@ impl Elm327 {
     fn reset_protocol(&mut self) -> Result<()> {
         info!("Performing protocol reset");
 
-        // set to use automatic protocol selection
-        debug!(
-            "reset_protocol: got response {:?}",
-            self.serial_cmd("ATSP0")?
-        );
+        // show the address of the ECU that sent each response
+        let response = self.serial_cmd("ATH1")?;
+        debug!("reset_protocol: got response {:?}", response);
 
-        // perform the search
-        debug!(
-            "reset_protocol: got OBD response {:?}",
-            self.cmd(&[0x01, 0x00])?
-        );
+        // set to use automatic protocol selection
+        let response = self.serial_cmd("ATSP0")?;
+        debug!("reset_protocol: got response {:?}", response);
+
+        // perform the search; failing is not fatal, since the vehicle may just be turned off
+        match self.cmd(&[0x01, 0x00]) {
+            Ok(response) => debug!("reset_protocol: got OBD response {:?}", response),
+            // errors reported by the ELM327 itself have a retry hint
+            Err(e) if e.retry_hint().is_some() => info!("Protocol search failed: {}", e),
+            Err(e) => return Err(e),
+        }
 
         // get rid of extra data hanging around in the buffer
         self.flush_buffers()?;
@@ -269,7 +401,20 @@ impl Elm327 {
     fn read_into_queue(&mut self) -> Result<()> {
         let mut buf = [0u8; 16];
         loop {
-            let len = self.device.read(&mut buf)?;
+            let len = match self.device.read(&mut buf) {
+                Ok(len) => len,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::TimedOut
+                            | std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    0
+                }
+                Err(e) => return Err(e.into()),
+            };
             if len > 0 {
                 self.buffer.extend(&buf[0..len]);
                 trace!(
@@ -309,3 +454,146 @@ impl Elm327 {
         }
     }
 }
+
+/// Check an ELM327 response for status messages
+///
+/// Informational messages are removed, and messages that indicate an error are returned as the
+/// matching [Error].
+pub(super) fn check_status(response: String) -> Result<String> {
+    let mut lines = Vec::new();
+    for line in response.split('\n') {
+        let status = line.trim();
+        if status == "SEARCHING..." || (status.starts_with("BUS INIT:") && status.ends_with("OK")) {
+            continue;
+        }
+
+        let error = match status {
+            "NO DATA" => Error::NoData,
+            "UNABLE TO CONNECT" => Error::UnableToConnect,
+            "BUS BUSY" => Error::BusBusy,
+            "BUS ERROR" => Error::BusError,
+            "CAN ERROR" => Error::CanError,
+            "DATA ERROR" => Error::DataError,
+            "FB ERROR" => Error::FeedbackError,
+            "BUFFER FULL" => Error::BufferFull,
+            "STOPPED" => Error::Stopped,
+            "LV RESET" => Error::LowVoltageReset,
+            "ACT ALERT" => Error::ActivityAlert,
+            "?" => Error::UnknownCommand,
+            s if s.starts_with("BUS INIT:") => Error::BusInitError,
+            // these are printed after the data that had the error
+            s if s.ends_with("<RX ERROR") => Error::RxError,
+            s if s.ends_with("<DATA ERROR") => Error::DataError,
+            _ => {
+                lines.push(line);
+                continue;
+            }
+        };
+        debug!("check_status: got {:?} in response {:?}", status, response);
+        return Err(error);
+    }
+
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+
+    /// Start an ELM327 stand-in on a local TCP port, and return the port
+    ///
+    /// Each command is echoed and answered with the reply from `respond`, followed by the
+    /// prompt. Commands that `respond` returns `None` for are ignored, as if the adapter hung.
+    fn spawn_elm327(respond: fn(&str) -> Option<&'static str>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut line = Vec::new();
+            let mut byte = [0];
+            while matches!(stream.read(&mut byte), Ok(1)) {
+                match byte[0] {
+                    b'\r' => {
+                        let command = String::from_utf8(std::mem::take(&mut line)).unwrap();
+                        if let Some(reply) = respond(&command) {
+                            let reply = format!("{}\r{}\r\r>", command, reply);
+                            if stream.write_all(reply.as_bytes()).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    b'\n' => (),
+                    b => line.push(b),
+                }
+            }
+        });
+
+        port
+    }
+
+    fn respond(command: &str) -> Option<&'static str> {
+        Some(match command {
+            "ATZ" => "ELM327 v1.5",
+            "ATH1" | "ATSP0" => "OK",
+            "0100" => "SEARCHING...\r7E8 06 41 00 BE 3E B8 11",
+            "ATDPN" => "A",
+            "010D" => "7E8 03 41 0D 32",
+            // the adapter stops responding
+            "010C" => return None,
+            _ => "?",
+        })
+    }
+
+    fn connect(port: u16) -> Elm327 {
+        Elm327::from_tcp(
+            "127.0.0.1",
+            port,
+            time::Duration::from_secs(1),
+            time::Duration::from_millis(10),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tcp_command() {
+        let mut device = connect(spawn_elm327(respond));
+
+        // protocol A (SAE J1939), not an automatically found protocol with no number
+        assert_eq!(device.header_format().unwrap(), HeaderFormat::Can29);
+        let response = device.cmd(&[0x01, 0x0D]).unwrap().unwrap();
+        assert_eq!(response.trim(), "7E8 03 41 0D 32");
+    }
+
+    #[test]
+    fn tcp_read_timeout() {
+        let mut device = connect(spawn_elm327(respond));
+
+        let start = time::Instant::now();
+        assert!(matches!(
+            device.cmd(&[0x01, 0x0C]),
+            Err(Error::Communication(_))
+        ));
+        assert!(start.elapsed() < time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn tcp_connection_refused() {
+        // find a port that nothing is listening on
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        assert!(Elm327::from_tcp(
+            "127.0.0.1",
+            port,
+            time::Duration::from_secs(1),
+            time::Duration::from_millis(10),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,1001 @@
+//! ISO-TP (ISO 15765-2) transport layer
+//!
+//! A CAN frame carries at most eight bytes, so longer messages are split into a first frame and
+//! a series of consecutive frames, with the receiver controlling the pace using flow control
+//! frames. Messages that fit are sent in a single frame.
+//!
+//! This module only builds and interprets frames; sending and receiving them is left to the
+//! caller, so it can be used by any CAN backend. A [Sender] splits a message into frames, and a
+//! [Receiver] reassembles one and says when flow control must be sent. Frames are passed around
+//! as their data bytes only, without the CAN identifier.
+//!
+//! # Example
+//! ```
+//! use obd2::device::isotp::{Config, ReceiveStatus, Receiver, Sender};
+//!
+//! // an ECU sending a VIN
+//! let message = b"\x49\x02\x011D4GP00R55B123456";
+//! let mut sender = Sender::new(Config::default(), message).unwrap();
+//! let mut receiver = Receiver::new(Config::default());
+//!
+//! let first = sender.next_frame().unwrap();
+//! assert_eq!(first, [0x10, 0x14, 0x49, 0x02, 0x01, b'1', b'D', b'4']);
+//! assert!(sender.next_frame().is_none()); // waiting for flow control
+//!
+//! let ReceiveStatus::FlowControl(flow_control) = receiver.on_frame(&first).unwrap() else {
+//!     panic!("expected flow control");
+//! };
+//! assert_eq!(flow_control, [0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+//! sender.on_flow_control(&flow_control).unwrap();
+//!
+//! let mut status = ReceiveStatus::Incomplete;
+//! while let Some(frame) = sender.next_frame() {
+//!     status = receiver.on_frame(&frame).unwrap();
+//! }
+//! assert!(sender.is_complete());
+//! assert_eq!(status, ReceiveStatus::Complete(message.to_vec()));
+//! ```
+
+use std::time;
+
+/// The largest message that can be sent with a 12-bit length
+const MAX_MESSAGE_LENGTH: usize = 0xfff;
+
+/// The length of a classic CAN frame
+const FRAME_LENGTH: usize = 8;
+
+/// How the address of a message is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Addressing {
+    /// The address is only the CAN identifier (used by OBD-II)
+    Normal,
+
+    /// The first byte of each frame is an additional address
+    ///
+    /// Transmitted frames are prefixed with `tx_address`, and received frames must be prefixed
+    /// with `rx_address`.
+    Extended {
+        /// Address byte added to frames that are sent
+        tx_address: u8,
+        /// Address byte expected on frames that are received
+        rx_address: u8,
+    },
+}
+
+/// ISO-TP parameters
+///
+/// The defaults are the values required for OBD-II by ISO 15765-4: no limits on block size or
+/// separation time, frames padded to eight bytes, no wait frames, and timeouts of 25 ms (N_As),
+/// 75 ms (N_Bs), and 150 ms (N_Cr).
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Number of consecutive frames the peer may send before waiting for flow control, or zero
+    /// for no limit
+    pub block_size: u8,
+
+    /// Minimum time the peer must wait between consecutive frames
+    ///
+    /// Values from 100 to 900 µs in 100 µs increments, and up to 127 ms in 1 ms increments, can
+    /// be represented; other values are rounded down.
+    pub separation_time: time::Duration,
+
+    /// The byte used to pad frames to eight bytes, or `None` to send frames of minimal length
+    pub padding: Option<u8>,
+
+    /// How many wait flow control frames in a row the sender accepts before aborting (N_WFTmax)
+    pub max_wait_frames: u8,
+
+    /// How long the transmission of a frame may take (N_As)
+    ///
+    /// This is not used by this module since it does not send frames, but is provided for the
+    /// backends that do.
+    pub n_as: time::Duration,
+
+    /// How long to wait for flow control after a first frame or a complete block (N_Bs)
+    pub n_bs: time::Duration,
+
+    /// How long to wait for the next consecutive frame (N_Cr)
+    pub n_cr: time::Duration,
+
+    /// How the address of a message is encoded
+    pub addressing: Addressing,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            block_size: 0,
+            separation_time: time::Duration::ZERO,
+            padding: Some(0x00),
+            max_wait_frames: 0,
+            n_as: time::Duration::from_millis(25),
+            n_bs: time::Duration::from_millis(75),
+            n_cr: time::Duration::from_millis(150),
+            addressing: Addressing::Normal,
+        }
+    }
+}
+
+impl Config {
+    /// Number of data bytes before the protocol control information
+    fn address_length(&self) -> usize {
+        match self.addressing {
+            Addressing::Normal => 0,
+            Addressing::Extended { .. } => 1,
+        }
+    }
+}
+
+/// Status sent in a flow control frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowStatus {
+    /// The sender may continue with the next block
+    ContinueToSend,
+    /// The sender must wait for another flow control frame
+    Wait,
+    /// The message is too large for the receiver, and the transfer is aborted
+    Overflow,
+}
+
+/// A single ISO-TP frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// An entire message that fits in one frame
+    Single(Vec<u8>),
+
+    /// The start of a message that is split over multiple frames
+    First {
+        /// Length of the entire message
+        length: usize,
+        /// The first part of the message
+        data: Vec<u8>,
+    },
+
+    /// A continuation of a message started by a [First](Self::First) frame
+    Consecutive {
+        /// Sequence number, which counts up from 1 and wraps from 15 to 0
+        sequence_number: u8,
+        /// The next part of the message
+        data: Vec<u8>,
+    },
+
+    /// Sent by the receiver to control the sender
+    FlowControl {
+        /// Whether the sender can continue
+        status: FlowStatus,
+        /// Number of frames to send before waiting for flow control, or zero for no limit
+        block_size: u8,
+        /// Minimum time between consecutive frames
+        separation_time: time::Duration,
+    },
+}
+
+impl Frame {
+    /// Decode the data bytes of a CAN frame
+    ///
+    /// Any padding after the data of a consecutive frame is included in the result, and must be
+    /// removed using the length of the message.
+    pub fn decode(frame: &[u8], addressing: Addressing) -> Result<Self> {
+        let invalid = || Error::InvalidFrame(frame.to_vec());
+
+        let data = match addressing {
+            Addressing::Normal => frame,
+            Addressing::Extended { rx_address, .. } => match frame.split_first() {
+                Some((address, data)) if *address == rx_address => data,
+                Some((address, _)) => return Err(Error::WrongAddress(rx_address, *address)),
+                None => return Err(invalid()),
+            },
+        };
+
+        let pci = *data.first().ok_or_else(invalid)?;
+        match pci >> 4 {
+            0 => {
+                let length = usize::from(pci & 0x0f);
+                if length == 0 {
+                    return Err(invalid());
+                }
+                let data = data.get(1..=length).ok_or_else(invalid)?;
+                Ok(Frame::Single(data.to_vec()))
+            }
+            1 => {
+                let length =
+                    (usize::from(pci & 0x0f) << 8) | usize::from(*data.get(1).ok_or_else(invalid)?);
+                Ok(Frame::First {
+                    length,
+                    data: data[2..].to_vec(),
+                })
+            }
+            2 => Ok(Frame::Consecutive {
+                sequence_number: pci & 0x0f,
+                data: data[1..].to_vec(),
+            }),
+            3 => {
+                let status = match pci & 0x0f {
+                    0 => FlowStatus::ContinueToSend,
+                    1 => FlowStatus::Wait,
+                    2 => FlowStatus::Overflow,
+                    _ => return Err(invalid()),
+                };
+                let block_size = *data.get(1).ok_or_else(invalid)?;
+                let separation_time = decode_separation_time(*data.get(2).ok_or_else(invalid)?);
+                Ok(Frame::FlowControl {
+                    status,
+                    block_size,
+                    separation_time,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Encode the frame into the data bytes of a CAN frame
+    ///
+    /// The frame's data must fit; this is always the case for frames produced by a [Sender].
+    pub fn encode(&self, config: &Config) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(FRAME_LENGTH);
+        if let Addressing::Extended { tx_address, .. } = config.addressing {
+            frame.push(tx_address);
+        }
+
+        match self {
+            Frame::Single(data) => {
+                frame.push(data.len() as u8);
+                frame.extend_from_slice(data);
+            }
+            Frame::First { length, data } => {
+                frame.push(0x10 | (length >> 8) as u8);
+                frame.push(*length as u8);
+                frame.extend_from_slice(data);
+            }
+            Frame::Consecutive {
+                sequence_number,
+                data,
+            } => {
+                frame.push(0x20 | (sequence_number & 0x0f));
+                frame.extend_from_slice(data);
+            }
+            Frame::FlowControl {
+                status,
+                block_size,
+                separation_time,
+            } => {
+                frame.push(match status {
+                    FlowStatus::ContinueToSend => 0x30,
+                    FlowStatus::Wait => 0x31,
+                    FlowStatus::Overflow => 0x32,
+                });
+                frame.push(*block_size);
+                frame.push(encode_separation_time(*separation_time));
+            }
+        }
+
+        if let Some(padding) = config.padding {
+            frame.resize(frame.len().max(FRAME_LENGTH), padding);
+        }
+
+        frame
+    }
+}
+
+fn decode_separation_time(st_min: u8) -> time::Duration {
+    match st_min {
+        0x00..=0x7f => time::Duration::from_millis(st_min.into()),
+        0xf1..=0xf9 => time::Duration::from_micros(u64::from(st_min - 0xf0) * 100),
+        // reserved values must be treated as the longest separation time
+        _ => time::Duration::from_millis(0x7f),
+    }
+}
+
+fn encode_separation_time(separation_time: time::Duration) -> u8 {
+    let micros = separation_time.as_micros();
+    if micros >= 1000 {
+        (separation_time.as_millis().min(0x7f)) as u8
+    } else if micros >= 100 {
+        0xf0 + (micros / 100) as u8
+    } else {
+        0
+    }
+}
+
+/// Splits a message into frames
+///
+/// Call [next_frame](Self::next_frame) and send each frame it returns, waiting for the
+/// [separation time](Self::separation_time) between frames. When it returns `None`, either the
+/// message is [complete](Self::is_complete) or the next received flow control frame must be
+/// passed to [on_flow_control](Self::on_flow_control).
+///
+/// # Example
+/// ```
+/// use obd2::device::isotp::{Addressing, Config, Sender};
+///
+/// let config = Config {
+///     addressing: Addressing::Extended {
+///         tx_address: 0xF1,
+///         rx_address: 0x10,
+///     },
+///     ..Config::default()
+/// };
+///
+/// // a request that fits in a single frame, after the address byte
+/// let mut sender = Sender::new(config.clone(), &[0x22, 0xF1, 0x90]).unwrap();
+/// assert_eq!(sender.next_frame(), Some(vec![0xF1, 0x03, 0x22, 0xF1, 0x90, 0x00, 0x00, 0x00]));
+/// assert!(sender.is_complete());
+///
+/// // a request that does not fit, with the receiver asking for 1 ms between frames
+/// let mut sender = Sender::new(config, &[1, 2, 3, 4, 5, 6, 7]).unwrap();
+/// assert_eq!(sender.next_frame(), Some(vec![0xF1, 0x10, 0x07, 1, 2, 3, 4, 5]));
+/// assert_eq!(sender.next_frame(), None);
+/// sender.on_flow_control(&[0x10, 0x30, 0x00, 0x01]).unwrap();
+/// assert_eq!(sender.separation_time(), std::time::Duration::from_millis(1));
+/// assert_eq!(sender.next_frame(), Some(vec![0xF1, 0x21, 6, 7, 0x00, 0x00, 0x00, 0x00]));
+/// assert!(sender.is_complete());
+/// ```
+#[derive(Debug)]
+pub struct Sender {
+    config: Config,
+    data: Vec<u8>,
+    offset: usize,
+    sequence_number: u8,
+    state: SenderState,
+    block_remaining: Option<u8>,
+    separation_time: time::Duration,
+    wait_frames: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SenderState {
+    Ready,
+    AwaitingFlowControl(time::Instant),
+    Done,
+}
+
+impl Sender {
+    /// Prepare to send a message
+    ///
+    /// An error is returned if the message is empty or too long to send.
+    pub fn new(config: Config, data: &[u8]) -> Result<Self> {
+        if data.is_empty() || data.len() > MAX_MESSAGE_LENGTH {
+            return Err(Error::MessageLength(data.len()));
+        }
+
+        Ok(Sender {
+            config,
+            data: data.to_vec(),
+            offset: 0,
+            sequence_number: 1,
+            state: SenderState::Ready,
+            block_remaining: None,
+            separation_time: time::Duration::ZERO,
+            wait_frames: 0,
+        })
+    }
+
+    /// Get the next frame to send, if one can be sent now
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        if self.state != SenderState::Ready {
+            return None;
+        }
+
+        let space = FRAME_LENGTH - self.config.address_length();
+        let frame = if self.offset == 0 {
+            if self.data.len() < space {
+                self.offset = self.data.len();
+                self.state = SenderState::Done;
+                Frame::Single(self.data.clone())
+            } else {
+                self.offset = space - 2;
+                self.state = SenderState::AwaitingFlowControl(time::Instant::now());
+                Frame::First {
+                    length: self.data.len(),
+                    data: self.data[..self.offset].to_vec(),
+                }
+            }
+        } else {
+            let end = (self.offset + space - 1).min(self.data.len());
+            let frame = Frame::Consecutive {
+                sequence_number: self.sequence_number,
+                data: self.data[self.offset..end].to_vec(),
+            };
+            self.offset = end;
+            self.sequence_number = (self.sequence_number + 1) % 0x10;
+
+            if self.offset == self.data.len() {
+                self.state = SenderState::Done;
+            } else if let Some(remaining) = self.block_remaining.as_mut() {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    self.state = SenderState::AwaitingFlowControl(time::Instant::now());
+                }
+            }
+
+            frame
+        };
+
+        Some(frame.encode(&self.config))
+    }
+
+    /// Handle a flow control frame from the receiver
+    pub fn on_flow_control(&mut self, frame: &[u8]) -> Result<()> {
+        if !matches!(self.state, SenderState::AwaitingFlowControl(_)) {
+            return Err(Error::UnexpectedFrame(frame.to_vec()));
+        }
+
+        match Frame::decode(frame, self.config.addressing)? {
+            Frame::FlowControl {
+                status: FlowStatus::ContinueToSend,
+                block_size,
+                separation_time,
+            } => {
+                self.block_remaining = (block_size != 0).then_some(block_size);
+                self.separation_time = separation_time;
+                self.wait_frames = 0;
+                self.state = SenderState::Ready;
+                Ok(())
+            }
+            Frame::FlowControl {
+                status: FlowStatus::Wait,
+                ..
+            } => {
+                if self.wait_frames >= self.config.max_wait_frames {
+                    self.state = SenderState::Done;
+                    return Err(Error::TooManyWaitFrames);
+                }
+                self.wait_frames += 1;
+                self.state = SenderState::AwaitingFlowControl(time::Instant::now());
+                Ok(())
+            }
+            Frame::FlowControl {
+                status: FlowStatus::Overflow,
+                ..
+            } => {
+                self.state = SenderState::Done;
+                Err(Error::Overflow)
+            }
+            _ => Err(Error::UnexpectedFrame(frame.to_vec())),
+        }
+    }
+
+    /// The minimum time to wait between consecutive frames, as requested by the receiver
+    pub fn separation_time(&self) -> time::Duration {
+        self.separation_time
+    }
+
+    /// Whether every frame of the message has been returned
+    pub fn is_complete(&self) -> bool {
+        self.state == SenderState::Done
+    }
+
+    /// Whether flow control has not been received within N_Bs
+    pub fn timed_out(&self) -> bool {
+        match self.state {
+            SenderState::AwaitingFlowControl(since) => since.elapsed() > self.config.n_bs,
+            _ => false,
+        }
+    }
+}
+
+/// The result of passing a frame to a [Receiver]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveStatus {
+    /// A message was completely received
+    Complete(Vec<u8>),
+    /// This flow control frame must be sent before the sender will continue
+    FlowControl(Vec<u8>),
+    /// More frames are needed
+    Incomplete,
+}
+
+/// Reassembles a message from frames
+///
+/// Pass each frame from the sender to [on_frame](Self::on_frame), sending any flow control frames
+/// it asks for, until the message is complete.
+///
+/// # Example
+/// ```
+/// use obd2::device::isotp::{Config, Error, ReceiveStatus, Receiver};
+///
+/// let config = Config {
+///     block_size: 2,
+///     padding: None,
+///     ..Config::default()
+/// };
+/// let mut receiver = Receiver::new(config);
+///
+/// let flow_control = ReceiveStatus::FlowControl(vec![0x30, 0x02, 0x00]);
+/// assert_eq!(receiver.on_frame(&[0x10, 0x20, 1, 2, 3, 4, 5, 6]), Ok(flow_control.clone()));
+/// assert_eq!(receiver.on_frame(&[0x21, 7, 8, 9, 10, 11, 12, 13]), Ok(ReceiveStatus::Incomplete));
+/// // the block is complete, so the sender must be told to continue
+/// assert_eq!(receiver.on_frame(&[0x22, 14, 15, 16, 17, 18, 19, 20]), Ok(flow_control));
+/// assert_eq!(
+///     receiver.on_frame(&[0x23, 21, 22, 23, 24, 25, 26, 27]),
+///     Ok(ReceiveStatus::Incomplete)
+/// );
+///
+/// // a lost frame discards the message
+/// assert_eq!(
+///     receiver.on_frame(&[0x25, 0, 0, 0, 0, 0, 0, 0]),
+///     Err(Error::WrongSequenceNumber(4, 5))
+/// );
+/// assert!(!receiver.is_receiving());
+/// ```
+#[derive(Debug)]
+pub struct Receiver {
+    config: Config,
+    partial: Option<PartialMessage>,
+}
+
+#[derive(Debug)]
+struct PartialMessage {
+    length: usize,
+    sequence_number: u8,
+    data: Vec<u8>,
+    block_remaining: u8,
+    last_frame: time::Instant,
+}
+
+impl Receiver {
+    /// Prepare to receive messages
+    pub fn new(config: Config) -> Self {
+        Receiver {
+            config,
+            partial: None,
+        }
+    }
+
+    /// Handle a frame from the sender
+    ///
+    /// If an error is returned, any partially received message is discarded.
+    pub fn on_frame(&mut self, frame: &[u8]) -> Result<ReceiveStatus> {
+        let decoded = Frame::decode(frame, self.config.addressing);
+        let decoded = match decoded {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                self.partial = None;
+                return Err(e);
+            }
+        };
+
+        match decoded {
+            Frame::Single(data) => {
+                // a new message replaces any in progress
+                self.partial = None;
+                Ok(ReceiveStatus::Complete(data))
+            }
+            Frame::First { length, data } => {
+                if length < FRAME_LENGTH - self.config.address_length() {
+                    self.partial = None;
+                    return Err(Error::InvalidFrame(frame.to_vec()));
+                }
+
+                self.partial = Some(PartialMessage {
+                    length,
+                    sequence_number: 1,
+                    data,
+                    block_remaining: self.config.block_size,
+                    last_frame: time::Instant::now(),
+                });
+                Ok(ReceiveStatus::FlowControl(self.flow_control()))
+            }
+            Frame::Consecutive {
+                sequence_number,
+                data,
+            } => {
+                let Some(partial) = self.partial.as_mut() else {
+                    return Err(Error::UnexpectedFrame(frame.to_vec()));
+                };
+                if sequence_number != partial.sequence_number {
+                    let expected = partial.sequence_number;
+                    self.partial = None;
+                    return Err(Error::WrongSequenceNumber(expected, sequence_number));
+                }
+
+                partial.sequence_number = (partial.sequence_number + 1) % 0x10;
+                partial.last_frame = time::Instant::now();
+                partial.data.extend_from_slice(&data);
+
+                if partial.data.len() >= partial.length {
+                    let mut data = std::mem::take(&mut partial.data);
+                    data.truncate(partial.length);
+                    self.partial = None;
+                    return Ok(ReceiveStatus::Complete(data));
+                }
+
+                if partial.block_remaining != 0 {
+                    partial.block_remaining -= 1;
+                    if partial.block_remaining == 0 {
+                        partial.block_remaining = self.config.block_size;
+                        return Ok(ReceiveStatus::FlowControl(self.flow_control()));
+                    }
+                }
+
+                Ok(ReceiveStatus::Incomplete)
+            }
+            Frame::FlowControl { .. } => Err(Error::UnexpectedFrame(frame.to_vec())),
+        }
+    }
+
+    /// Whether a message is partially received
+    pub fn is_receiving(&self) -> bool {
+        self.partial.is_some()
+    }
+
+    /// Whether the next consecutive frame has not been received within N_Cr
+    pub fn timed_out(&self) -> bool {
+        self.partial
+            .as_ref()
+            .is_some_and(|p| p.last_frame.elapsed() > self.config.n_cr)
+    }
+
+    /// Discard any partially received message
+    pub fn reset(&mut self) {
+        self.partial = None;
+    }
+
+    fn flow_control(&self) -> Vec<u8> {
+        Frame::FlowControl {
+            status: FlowStatus::ContinueToSend,
+            block_size: self.config.block_size,
+            separation_time: self.config.separation_time,
+        }
+        .encode(&self.config)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// An ISO-TP protocol error
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The frame could not be decoded
+    #[error("Invalid frame: `{0:02X?}`")]
+    InvalidFrame(Vec<u8>),
+
+    /// The frame is valid, but was not expected at this point of the transfer
+    #[error("Unexpected frame: `{0:02X?}`")]
+    UnexpectedFrame(Vec<u8>),
+
+    /// The extended address of a frame did not match: expected the first, got the second
+    #[error("Wrong address: expected `{0:02X}`, got `{1:02X}`")]
+    WrongAddress(u8, u8),
+
+    /// A consecutive frame was lost or repeated: expected the first, got the second
+    #[error("Wrong sequence number: expected `{0:X}`, got `{1:X}`")]
+    WrongSequenceNumber(u8, u8),
+
+    /// The receiver cannot accept a message this long
+    #[error("Receiver overflow")]
+    Overflow,
+
+    /// The receiver sent more wait flow control frames in a row than allowed
+    #[error("Too many wait frames")]
+    TooManyWaitFrames,
+
+    /// The message is empty or too long to send
+    #[error("Invalid message length: `{0}`")]
+    MessageLength(usize),
+
+    /// The peer did not respond in time; the name of the timeout (like `"N_Cr"`) is included
+    #[error("Timeout: `{0}`")]
+    Timeout(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    /// Feed flow control frames to a sender whenever it stops, and collect the frames it sends
+    fn send_all(sender: &mut Sender, flow_control: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        while !sender.is_complete() {
+            match sender.next_frame() {
+                Some(frame) => frames.push(frame),
+                None => sender.on_flow_control(flow_control).unwrap(),
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn flow_control_wait() {
+        let config = Config {
+            max_wait_frames: 2,
+            ..Config::default()
+        };
+        let mut sender = Sender::new(config, &[0; 10]).unwrap();
+        sender.next_frame().unwrap();
+
+        sender.on_flow_control(&[0x31, 0, 0]).unwrap();
+        assert_eq!(sender.next_frame(), None);
+        sender.on_flow_control(&[0x31, 0, 0]).unwrap();
+        assert_eq!(sender.next_frame(), None);
+        sender.on_flow_control(&[0x30, 0, 0]).unwrap();
+        assert_eq!(
+            sender.next_frame(),
+            Some(vec![0x21, 0, 0, 0, 0, 0x00, 0x00, 0x00])
+        );
+        assert!(sender.is_complete());
+    }
+
+    #[test]
+    fn flow_control_too_many_waits() {
+        let config = Config {
+            max_wait_frames: 1,
+            ..Config::default()
+        };
+        let mut sender = Sender::new(config, &[0; 10]).unwrap();
+        sender.next_frame().unwrap();
+
+        sender.on_flow_control(&[0x31, 0, 0]).unwrap();
+        assert_eq!(
+            sender.on_flow_control(&[0x31, 0, 0]),
+            Err(Error::TooManyWaitFrames)
+        );
+        assert!(sender.is_complete());
+        assert_eq!(sender.next_frame(), None);
+
+        // ISO 15765-4 does not allow wait frames at all
+        let mut sender = Sender::new(Config::default(), &[0; 10]).unwrap();
+        sender.next_frame().unwrap();
+        assert_eq!(
+            sender.on_flow_control(&[0x31, 0, 0]),
+            Err(Error::TooManyWaitFrames)
+        );
+    }
+
+    #[test]
+    fn wait_count_resets_after_continue() {
+        let config = Config {
+            max_wait_frames: 1,
+            ..Config::default()
+        };
+        let mut sender = Sender::new(config, &[0; 20]).unwrap();
+        sender.next_frame().unwrap();
+
+        sender.on_flow_control(&[0x31, 0, 0]).unwrap();
+        sender.on_flow_control(&[0x30, 1, 0]).unwrap();
+        sender.next_frame().unwrap();
+        assert_eq!(sender.next_frame(), None);
+        sender.on_flow_control(&[0x31, 0, 0]).unwrap();
+        sender.on_flow_control(&[0x30, 1, 0]).unwrap();
+        assert!(sender.next_frame().is_some());
+    }
+
+    #[test]
+    fn flow_control_overflow() {
+        let mut sender = Sender::new(Config::default(), &[0; 10]).unwrap();
+        sender.next_frame().unwrap();
+        assert_eq!(sender.on_flow_control(&[0x32, 0, 0]), Err(Error::Overflow));
+        assert!(sender.is_complete());
+        assert_eq!(sender.next_frame(), None);
+    }
+
+    #[test]
+    fn unexpected_flow_control() {
+        let mut sender = Sender::new(Config::default(), &[1, 2, 3]).unwrap();
+        assert_eq!(
+            sender.on_flow_control(&[0x30, 0, 0]),
+            Err(Error::UnexpectedFrame(vec![0x30, 0, 0]))
+        );
+    }
+
+    #[test]
+    fn sender_n_bs_timeout() {
+        let config = Config {
+            n_bs: time::Duration::from_millis(10),
+            ..Config::default()
+        };
+        let mut sender = Sender::new(config, &[0; 10]).unwrap();
+        assert!(!sender.timed_out());
+        sender.next_frame().unwrap();
+        assert!(!sender.timed_out());
+        thread::sleep(time::Duration::from_millis(20));
+        assert!(sender.timed_out());
+
+        // flow control stops the timer
+        sender.on_flow_control(&[0x30, 0, 0]).unwrap();
+        assert!(!sender.timed_out());
+    }
+
+    #[test]
+    fn receiver_n_cr_timeout() {
+        let config = Config {
+            n_cr: time::Duration::from_millis(10),
+            ..Config::default()
+        };
+        let mut receiver = Receiver::new(config);
+        assert!(!receiver.timed_out());
+        receiver.on_frame(&[0x10, 0x10, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(!receiver.timed_out());
+        thread::sleep(time::Duration::from_millis(20));
+        assert!(receiver.timed_out());
+
+        // a consecutive frame restarts the timer
+        receiver.on_frame(&[0x21, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(!receiver.timed_out());
+
+        receiver.reset();
+        assert!(!receiver.is_receiving());
+        assert!(!receiver.timed_out());
+    }
+
+    #[test]
+    fn sequence_number_wraps() {
+        // a first frame and 17 consecutive frames, so the sequence number wraps from F to 0
+        let message: Vec<u8> = (0..6 + 17 * 7).map(|i| i as u8).collect();
+        let mut sender = Sender::new(Config::default(), &message).unwrap();
+        let frames = send_all(&mut sender, &[0x30, 0, 0]);
+        assert_eq!(frames.len(), 18);
+        let sequence_numbers: Vec<u8> = frames[1..].iter().map(|f| f[0]).collect();
+        assert_eq!(sequence_numbers[14], 0x2F);
+        assert_eq!(sequence_numbers[15], 0x20);
+        assert_eq!(sequence_numbers[16], 0x21);
+
+        let mut receiver = Receiver::new(Config::default());
+        let mut status = ReceiveStatus::Incomplete;
+        for frame in &frames {
+            status = receiver.on_frame(frame).unwrap();
+        }
+        assert_eq!(status, ReceiveStatus::Complete(message));
+    }
+
+    #[test]
+    fn wrong_sequence_number() {
+        let mut receiver = Receiver::new(Config::default());
+        receiver.on_frame(&[0x10, 0x14, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(
+            receiver.on_frame(&[0x21, 0, 0, 0, 0, 0, 0, 0]),
+            Ok(ReceiveStatus::Incomplete)
+        );
+        // a repeated frame
+        assert_eq!(
+            receiver.on_frame(&[0x21, 0, 0, 0, 0, 0, 0, 0]),
+            Err(Error::WrongSequenceNumber(2, 1))
+        );
+        assert!(!receiver.is_receiving());
+
+        // without a first frame, consecutive frames are unexpected
+        assert_eq!(
+            receiver.on_frame(&[0x22, 0, 0, 0, 0, 0, 0, 0]),
+            Err(Error::UnexpectedFrame(vec![0x22, 0, 0, 0, 0, 0, 0, 0]))
+        );
+    }
+
+    #[test]
+    fn block_size_and_separation_time() {
+        let mut sender = Sender::new(Config::default(), &[0; 30]).unwrap();
+        sender.next_frame().unwrap();
+        assert_eq!(sender.next_frame(), None);
+
+        // two frames per block, 5 ms apart
+        sender.on_flow_control(&[0x30, 0x02, 0x05]).unwrap();
+        assert_eq!(sender.separation_time(), time::Duration::from_millis(5));
+        assert_eq!(sender.next_frame().unwrap()[0], 0x21);
+        assert_eq!(sender.next_frame().unwrap()[0], 0x22);
+        assert_eq!(sender.next_frame(), None);
+        assert!(!sender.is_complete());
+
+        // 300 µs apart and no block limit for the rest
+        sender.on_flow_control(&[0x30, 0x00, 0xF3]).unwrap();
+        assert_eq!(sender.separation_time(), time::Duration::from_micros(300));
+        assert_eq!(sender.next_frame().unwrap()[0], 0x23);
+        assert_eq!(sender.next_frame().unwrap()[0], 0x24);
+        assert!(sender.is_complete());
+
+        // a reserved separation time is the longest one
+        let mut sender = Sender::new(Config::default(), &[0; 10]).unwrap();
+        sender.next_frame().unwrap();
+        sender.on_flow_control(&[0x30, 0x00, 0x80]).unwrap();
+        assert_eq!(sender.separation_time(), time::Duration::from_millis(127));
+    }
+
+    #[test]
+    fn receiver_block_size_and_separation_time() {
+        let config = Config {
+            block_size: 1,
+            separation_time: time::Duration::from_micros(500),
+            ..Config::default()
+        };
+        let mut receiver = Receiver::new(config);
+        let flow_control = ReceiveStatus::FlowControl(vec![0x30, 0x01, 0xF5, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            receiver.on_frame(&[0x10, 0x14, 0, 0, 0, 0, 0, 0]),
+            Ok(flow_control.clone())
+        );
+        assert_eq!(
+            receiver.on_frame(&[0x21, 0, 0, 0, 0, 0, 0, 0]),
+            Ok(flow_control)
+        );
+    }
+
+    #[test]
+    fn send_without_padding() {
+        let config = Config {
+            padding: None,
+            ..Config::default()
+        };
+        let mut sender = Sender::new(config.clone(), &[0x01, 0x0C]).unwrap();
+        assert_eq!(sender.next_frame(), Some(vec![0x02, 0x01, 0x0C]));
+
+        let mut sender = Sender::new(config, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        let frames = send_all(&mut sender, &[0x30, 0, 0]);
+        assert_eq!(
+            frames,
+            [vec![0x10, 0x08, 1, 2, 3, 4, 5, 6], vec![0x21, 7, 8]]
+        );
+    }
+
+    #[test]
+    fn extended_address_mismatch() {
+        let config = Config {
+            addressing: Addressing::Extended {
+                tx_address: 0xF1,
+                rx_address: 0x10,
+            },
+            ..Config::default()
+        };
+        let mut receiver = Receiver::new(config.clone());
+        assert_eq!(
+            receiver.on_frame(&[0x10, 0x03, 0x41, 0x0D, 0x32]),
+            Ok(ReceiveStatus::Complete(vec![0x41, 0x0D, 0x32]))
+        );
+        assert_eq!(
+            receiver.on_frame(&[0x11, 0x03, 0x41, 0x0D, 0x32]),
+            Err(Error::WrongAddress(0x10, 0x11))
+        );
+
+        // the mismatch discards a message in progress
+        receiver
+            .on_frame(&[0x10, 0x10, 0x0A, 0, 0, 0, 0, 0])
+            .unwrap();
+        assert!(receiver.is_receiving());
+        assert_eq!(
+            receiver.on_frame(&[0x11, 0x21, 0, 0, 0, 0, 0, 0]),
+            Err(Error::WrongAddress(0x10, 0x11))
+        );
+        assert!(!receiver.is_receiving());
+
+        // and flow control for the sender must be addressed to it too
+        let mut sender = Sender::new(config, &[0; 10]).unwrap();
+        sender.next_frame().unwrap();
+        assert_eq!(
+            sender.on_flow_control(&[0x11, 0x30, 0, 0]),
+            Err(Error::WrongAddress(0x10, 0x11))
+        );
+    }
+
+    #[test]
+    fn first_frame_too_short() {
+        let mut receiver = Receiver::new(Config::default());
+        assert_eq!(
+            receiver.on_frame(&[0x10, 0x07, 1, 2, 3, 4, 5, 6]),
+            Err(Error::InvalidFrame(vec![0x10, 0x07, 1, 2, 3, 4, 5, 6]))
+        );
+        assert!(!receiver.is_receiving());
+
+        // eight bytes is the shortest message that needs a first frame
+        assert_eq!(
+            receiver.on_frame(&[0x10, 0x08, 1, 2, 3, 4, 5, 6]),
+            Ok(ReceiveStatus::FlowControl(vec![0x30, 0, 0, 0, 0, 0, 0, 0]))
+        );
+    }
+
+    #[test]
+    fn zero_length_single_frame() {
+        let mut receiver = Receiver::new(Config::default());
+        assert_eq!(
+            receiver.on_frame(&[0x00, 0, 0, 0, 0, 0, 0, 0]),
+            Err(Error::InvalidFrame(vec![0x00, 0, 0, 0, 0, 0, 0, 0]))
+        );
+        // a single frame that is longer than the CAN frame
+        assert_eq!(
+            receiver.on_frame(&[0x05, 1, 2]),
+            Err(Error::InvalidFrame(vec![0x05, 1, 2]))
+        );
+        assert_eq!(
+            Sender::new(Config::default(), &[]).err(),
+            Some(Error::MessageLength(0))
+        );
+    }
+}
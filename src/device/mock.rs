@@ -0,0 +1,299 @@
+use log::trace;
+use std::collections::VecDeque;
+
+use super::{Error, HeaderFormat, Obd2BaseDevice, Obd2Reader, Result};
+
+/// A scripted OBD-II device for testing without a vehicle
+///
+/// The mock is programmed with the requests it should receive and the reply to each, written as
+/// the text an [Elm327](super::Elm327) would return. Lines are separated by `\n` (a `\r` is also
+/// accepted), so replies from multiple ECUs, multi-line replies (`0: ...`, `1: ...`), and status
+/// messages like `NO DATA` can be scripted. Replies can also include [headers](Self::headers), to
+/// test responses from specific ECUs. A request can also be answered with a timeout or an error.
+///
+/// Each request is matched against the first unused expectation for the same bytes, so requests
+/// may be sent in any order, while repeated requests get their replies in the order they were
+/// added. After the test, [verify](Self::verify) checks that every expected request was sent.
+///
+/// This is only available with the `mock` feature.
+///
+/// # Example
+/// ```
+/// use obd2::{
+///     commands::Obd2DataRetrieval, device::Mock, Error, NegativeResponse, NegativeResponseCode,
+///     Obd2,
+/// };
+///
+/// let mut mock = Mock::new();
+/// mock.expect(&[0x01, 0x00], "41 00 08 1A 00 00\n41 00 00 10 00 00")
+///     .expect(&[0x01, 0x0D], "41 0D 32")
+///     .expect(&[0x01, 0x0C], "41 0C 1A F8\n41 0C 1A F0")
+///     .expect(&[0x09, 0x02], "014\n0: 49 02 01 31 44 34\n1: 47 50 30 30 52 35 35\n2: 42 31 32 33 34 35 36")
+///     .expect_timeout(&[0x01, 0x05])
+///     .expect(&[0x01, 0x0F], "7F 01 78\n7F 01 31");
+///
+/// let mut device = Obd2::new(mock);
+/// assert_eq!(device.get_speed().unwrap(), vec![50]);
+/// assert_eq!(device.get_rpm().unwrap(), vec![1726.0, 1724.0]);
+/// assert_eq!(device.get_vin().unwrap(), "1D4GP00R55B123456");
+/// assert!(device.get_engine_coolant_temperature().is_err());
+/// assert!(matches!(
+///     device.get_intake_air_temperature(),
+///     Err(Error::NegativeResponse(NegativeResponse {
+///         code: NegativeResponseCode::RequestOutOfRange,
+///         ..
+///     }))
+/// ));
+/// // fuel level is not in the supported PIDs, so it is not requested
+/// assert!(matches!(device.get_fuel_level(), Err(Error::Unsupported(0x01, 0x2F))));
+/// assert!(device.device().verify().is_ok());
+///
+/// // DTCs were never expected
+/// assert!(device.get_dtcs().is_err());
+/// assert!(device.device().verify().is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct Mock {
+    expectations: VecDeque<Expectation>,
+    unexpected: Vec<Vec<u8>>,
+    reply: Option<Reply>,
+    later: VecDeque<Reply>,
+    header_format: HeaderFormat,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    request: Vec<u8>,
+    reply: Reply,
+    later: VecDeque<Reply>,
+}
+
+#[derive(Debug)]
+enum Reply {
+    Response(Vec<u8>),
+    Timeout,
+    Error(Error),
+}
+
+impl Obd2BaseDevice for Mock {
+    /// Send an OBD-II command and get the reply
+    ///
+    /// Status messages in the reply are handled the same way as by the [Elm327](super::Elm327),
+    /// so that scripting `NO DATA` results in [Error::NoData].
+    fn cmd(&mut self, cmd: &[u8]) -> Result<Option<String>> {
+        self.send_cmd(cmd)?;
+        self.get_response()?
+            .and_then(|resp| String::from_utf8(resp).ok())
+            .map(super::elm327::check_status)
+            .transpose()
+    }
+
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        Ok(self.header_format)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.reply = None;
+        self.later.clear();
+        Ok(())
+    }
+
+    fn send_cmd(&mut self, data: &[u8]) -> Result<()> {
+        trace!("send_cmd: got {:02X?}", data);
+
+        match self.expectations.iter().position(|e| e.request == data) {
+            Some(idx) => {
+                let expectation = self.expectations.remove(idx).unwrap();
+                self.reply = Some(expectation.reply);
+                self.later = expectation.later;
+                Ok(())
+            }
+            None => {
+                self.unexpected.push(data.to_vec());
+                Err(Error::Communication(format!(
+                    "send_cmd: unexpected request {:02X?}",
+                    data
+                )))
+            }
+        }
+    }
+}
+
+impl Obd2Reader for Mock {
+    fn get_line(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            let line = match self.reply.as_mut() {
+                Some(Reply::Response(response)) => {
+                    match response.iter().position(|b| *b == b'\n') {
+                        Some(idx) => {
+                            let rest = response.split_off(idx + 1);
+                            let mut line = std::mem::replace(response, rest);
+                            line.pop();
+                            line
+                        }
+                        None => {
+                            let line = std::mem::take(response);
+                            self.reply = None;
+                            line
+                        }
+                    }
+                }
+                Some(_) => return self.get_response(),
+                None => return Ok(None),
+            };
+
+            // empty lines are skipped, as a real device would
+            if !line.is_empty() {
+                return Ok(Some(line));
+            }
+        }
+    }
+
+    fn get_response(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.reply.take().or_else(|| self.later.pop_front()) {
+            Some(Reply::Response(response)) => Ok(Some(response)),
+            Some(Reply::Error(e)) => Err(e),
+            Some(Reply::Timeout) | None => Ok(None),
+        }
+    }
+}
+
+impl Mock {
+    /// Create a mock that expects no requests
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect `request` to be sent, and reply with `response`
+    pub fn expect(&mut self, request: &[u8], response: &str) -> &mut Self {
+        self.push(
+            request,
+            Reply::Response(response.replace('\r', "\n").into_bytes()),
+        )
+    }
+
+    /// Expect `request` to be sent, and reply with nothing as if the device timed out
+    pub fn expect_timeout(&mut self, request: &[u8]) -> &mut Self {
+        self.push(request, Reply::Timeout)
+    }
+
+    /// Expect `request` to be sent, and fail when the reply is read
+    pub fn expect_error(&mut self, request: &[u8], error: Error) -> &mut Self {
+        self.push(request, Reply::Error(error))
+    }
+
+    /// Send `response` after the reply to the last expected request, when more is read
+    ///
+    /// This scripts an ECU that first answers with "response pending" (`7F <service> 78`), and
+    /// sends the real reply later.
+    ///
+    /// # Example
+    /// ```
+    /// use obd2::{commands::Obd2DataRetrieval, device::Mock, Obd2};
+    ///
+    /// let mut mock = Mock::new();
+    /// mock.expect(&[0x01, 0x00], "NO DATA")
+    ///     .expect(&[0x01, 0x0D], "7F 01 78")
+    ///     .then("41 0D 32");
+    ///
+    /// let mut device = Obd2::new(mock);
+    /// assert_eq!(device.get_speed().unwrap(), vec![50]);
+    /// ```
+    ///
+    /// # Panics
+    /// If no request is expected yet.
+    pub fn then(&mut self, response: &str) -> &mut Self {
+        let expectation = self
+            .expectations
+            .back_mut()
+            .expect("then: no request is expected yet");
+        expectation
+            .later
+            .push_back(Reply::Response(response.replace('\r', "\n").into_bytes()));
+        self
+    }
+
+    /// Set the format of the headers in the scripted replies
+    ///
+    /// By default, replies have no headers.
+    ///
+    /// # Example
+    /// ```
+    /// use obd2::{
+    ///     commands::Obd2DataRetrieval,
+    ///     device::{HeaderFormat, Mock},
+    ///     EcuAddress, Obd2,
+    /// };
+    ///
+    /// let mut mock = Mock::new();
+    /// mock.headers(HeaderFormat::Can11)
+    ///     .expect(&[0x01, 0x00], "7E8 06 41 00 00 08 00 00\n7E9 06 41 00 00 08 00 00")
+    ///     .expect(&[0x01, 0x0D], "7E9 03 41 0D 30\n7E8 03 41 0D 32")
+    ///     // the frames of a multi-frame response can be mixed with other responses
+    ///     .expect(
+    ///         &[0x09, 0x02],
+    ///         "7E8 10 14 49 02 01 31 44 34\n7E9 03 7F 09 12\n7E8 21 47 50 30 30 52 35 35\n\
+    ///          7E8 22 42 31 32 33 34 35 36",
+    ///     );
+    ///
+    /// let mut device = Obd2::new(mock);
+    /// let speeds = device.get_speed_by_ecu().unwrap();
+    /// assert_eq!(speeds[&EcuAddress::Can11(0x7E8)], 50);
+    /// assert_eq!(speeds[&EcuAddress::Can11(0x7E9)], 48);
+    /// assert_eq!(device.get_vin().unwrap(), "1D4GP00R55B123456");
+    /// ```
+    pub fn headers(&mut self, format: HeaderFormat) -> &mut Self {
+        self.header_format = format;
+        self
+    }
+
+    /// Check that every expected request was sent, and that no other requests were sent
+    pub fn verify(&self) -> Result<()> {
+        if self.expectations.is_empty() && self.unexpected.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Communication(format!(
+                "verify: requests not sent: {:02X?}, unexpected requests: {:02X?}",
+                self.expectations
+                    .iter()
+                    .map(|e| &e.request)
+                    .collect::<Vec<_>>(),
+                self.unexpected
+            )))
+        }
+    }
+
+    fn push(&mut self, request: &[u8], reply: Reply) -> &mut Self {
+        self.expectations.push_back(Expectation {
+            request: request.to_vec(),
+            reply,
+            later: VecDeque::new(),
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_line_never_returns_empty_lines() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "41 00 BE 3E B8 11\n\n41 00 80 00 00 00\n");
+        mock.send_cmd(&[0x01, 0x00]).unwrap();
+
+        assert_eq!(
+            mock.get_line().unwrap(),
+            (Some(b"41 00 BE 3E B8 11".to_vec()))
+        );
+        assert_eq!(
+            mock.get_line().unwrap(),
+            (Some(b"41 00 80 00 00 00".to_vec()))
+        );
+        assert_eq!(mock.get_line().unwrap(), None);
+        assert_eq!(mock.get_line().unwrap(), None);
+        assert_eq!(mock.get_response().unwrap(), None);
+        assert!(mock.verify().is_ok());
+    }
+}
@@ -3,6 +3,21 @@
 mod elm327;
 pub use elm327::Elm327;
 
+pub mod isotp;
+
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::Mock;
+
+#[cfg(all(target_os = "linux", feature = "socketcan"))]
+mod socketcan;
+#[cfg(all(target_os = "linux", feature = "socketcan"))]
+pub use self::socketcan::SocketCan;
+
+mod transport;
+pub use transport::Transport;
+
 type Result<T> = std::result::Result<T, Error>;
 
 /// A lower-level API for using an OBD-II device
@@ -27,6 +42,32 @@ pub trait Obd2BaseDevice: Obd2Reader {
         self.get_response()
             .map(|o| o.and_then(|resp| String::from_utf8(resp).ok()))
     }
+
+    /// Get the format of the headers at the start of each line of a reply
+    ///
+    /// This is called after a reply is received, so devices that detect the protocol
+    /// automatically may depend on the protocol being known by then. The default implementation
+    /// returns [HeaderFormat::None].
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        Ok(HeaderFormat::None)
+    }
+}
+
+/// The format of the headers in a reply, which identify the ECU that sent each line
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    /// No headers; each line is the data from one ECU, or a multi-frame response is split into
+    /// numbered lines (`0: AB CD ...`)
+    #[default]
+    None,
+    /// An 11-bit CAN ID followed by the ISO-TP frame, including the protocol control information
+    /// (`7E8 06 41 00 BE 3F A8 13`)
+    Can11,
+    /// A 29-bit CAN ID as four bytes followed by the ISO-TP frame (`18 DA F1 10 06 41 00 ...`)
+    Can29,
+    /// Three header bytes (priority, target address, and source address) followed by the data
+    /// and a checksum byte, as used by J1850, ISO 9141-2, and ISO 14230-4 (`48 6B 10 41 00 ...`)
+    Legacy,
 }
 
 /// An API for reading OBD-II response data
@@ -51,9 +92,15 @@ pub trait Obd2Reader {
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// An error with the underlying [FTDI device](ftdi::Device)
+    #[cfg(feature = "ftdi")]
     #[error("FTDI error: `{0:?}`")]
     Ftdi(ftdi::Error),
 
+    /// An error with the underlying [serial port](serialport::SerialPort)
+    #[cfg(feature = "serialport")]
+    #[error("Serial port error: `{0:?}`")]
+    Serial(serialport::Error),
+
     /// An I/O error in a low-level [std::io] stream operation
     #[error("IO error: `{0:?}`")]
     IO(std::io::Error),
@@ -61,16 +108,126 @@ pub enum Error {
     /// An OBD-II or interface device protocol error
     #[error("Communication error: `{0}`")]
     Communication(String),
+
+    /// An error in the [ISO-TP](isotp) transport layer
+    #[error("ISO-TP error: `{0}`")]
+    IsoTp(isotp::Error),
+
+    /// No ECU responded to the request, usually because it is not supported (`NO DATA`)
+    #[error("No data")]
+    NoData,
+
+    /// The device could not find a working protocol, or lost it (`UNABLE TO CONNECT`)
+    #[error("Unable to connect")]
+    UnableToConnect,
+
+    /// Initialization of an ISO 9141 or ISO 14230 bus failed (`BUS INIT: ...ERROR`)
+    #[error("Bus initialization error")]
+    BusInitError,
+
+    /// The bus was too busy to send a request (`BUS BUSY`)
+    #[error("Bus busy")]
+    BusBusy,
+
+    /// A generic bus fault, like a short circuit or an invalid voltage (`BUS ERROR`)
+    #[error("Bus error")]
+    BusError,
+
+    /// The CAN controller could not send or receive, usually because of a wrong bit rate or
+    /// wiring (`CAN ERROR`)
+    #[error("CAN error")]
+    CanError,
+
+    /// A response was received with an invalid checksum or format (`DATA ERROR`)
+    #[error("Data error")]
+    DataError,
+
+    /// The device did not see its own transmission on the bus (`FB ERROR`)
+    #[error("Feedback error")]
+    FeedbackError,
+
+    /// The device's receive buffer overflowed (`BUFFER FULL`)
+    #[error("Buffer full")]
+    BufferFull,
+
+    /// A CAN message was received with an error (`<RX ERROR`)
+    #[error("Receive error")]
+    RxError,
+
+    /// The operation was interrupted by another character being received (`STOPPED`)
+    #[error("Stopped")]
+    Stopped,
+
+    /// The device reset itself because its supply voltage was low (`LV RESET`)
+    #[error("Low voltage reset")]
+    LowVoltageReset,
+
+    /// The device saw no bus activity and is about to enter low power mode (`ACT ALERT`)
+    #[error("Activity alert")]
+    ActivityAlert,
+
+    /// The device did not understand the command (`?`)
+    #[error("Unknown command")]
+    UnknownCommand,
+}
+
+/// Whether a request that failed is worth repeating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryHint {
+    /// The error is likely temporary, and the same request may succeed
+    Retry,
+    /// The device or bus is in a bad state; [reset](Obd2BaseDevice::reset) the device first
+    ResetAndRetry,
+    /// Repeating the request will fail the same way
+    DoNotRetry,
+}
+
+impl Error {
+    /// Whether the request that caused this error is worth repeating
+    ///
+    /// Returns `None` for errors where that is not known, like I/O errors.
+    pub fn retry_hint(&self) -> Option<RetryHint> {
+        match self {
+            Error::BusInitError
+            | Error::BusBusy
+            | Error::DataError
+            | Error::BufferFull
+            | Error::RxError
+            | Error::Stopped => Some(RetryHint::Retry),
+            Error::UnableToConnect
+            | Error::BusError
+            | Error::CanError
+            | Error::FeedbackError
+            | Error::LowVoltageReset
+            | Error::ActivityAlert => Some(RetryHint::ResetAndRetry),
+            Error::NoData | Error::UnknownCommand => Some(RetryHint::DoNotRetry),
+            _ => None,
+        }
+    }
 }
 
+#[cfg(feature = "ftdi")]
 impl From<ftdi::Error> for Error {
     fn from(e: ftdi::Error) -> Self {
         Error::Ftdi(e)
     }
 }
 
+#[cfg(feature = "serialport")]
+impl From<serialport::Error> for Error {
+    fn from(e: serialport::Error) -> Self {
+        Error::Serial(e)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Error::IO(e)
     }
 }
+
+impl From<isotp::Error> for Error {
+    fn from(e: isotp::Error) -> Self {
+        Error::IsoTp(e)
+    }
+}
@@ -0,0 +1,281 @@
+use log::{debug, trace};
+use socketcan::{
+    CanFilter, CanFrame, CanSocket, EmbeddedFrame, Id, Socket, SocketOptions, StandardId,
+};
+use std::{collections::HashMap, time};
+
+use super::{
+    isotp::{self, ReceiveStatus, Receiver, Sender},
+    Error, HeaderFormat, Obd2BaseDevice, Obd2Reader, Result,
+};
+
+/// The functional address that OBD-II requests are broadcast to (ISO 15765-4, 11-bit)
+const FUNCTIONAL_REQUEST_ID: u16 = 0x7DF;
+
+/// The response address of the first ECU; ECU `n` (zero-indexed) responds on this plus `n`
+const FIRST_RESPONSE_ID: u16 = 0x7E8;
+
+/// The number of ECUs that can respond
+const RESPONSE_ID_COUNT: u16 = 8;
+
+/// The physical request address of an ECU is its response address minus this offset
+const PHYSICAL_REQUEST_OFFSET: u16 = 8;
+
+/// How long to keep waiting for frames after the last one was received
+const RESPONSE_TIMEOUT: time::Duration = time::Duration::from_millis(200);
+
+/// How long to wait for a response after an ECU asked for more time (P2* in ISO 15765-4)
+const PENDING_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// How long to wait for all responses to a request
+const TOTAL_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// A native SocketCAN interface
+///
+/// This communicates directly with the vehicle over a CAN bus using the Linux SocketCAN API,
+/// without an adapter like the [Elm327](super::Elm327) in between. Requests are sent to the
+/// ISO 15765-4 functional address `0x7DF`, and responses are accepted from `0x7E8` to `0x7EF`.
+/// Flow control is handled using the [isotp] module, and the reply is formatted like an ELM327's
+/// with headers enabled: one line per frame, starting with the CAN ID ([HeaderFormat::Can11]).
+pub struct SocketCan {
+    socket: CanSocket,
+    config: isotp::Config,
+    response: Option<Vec<u8>>,
+}
+
+impl Obd2BaseDevice for SocketCan {
+    /// Send an OBD-II command and get the reply
+    ///
+    /// If no ECU responds, [Error::NoData] is returned.
+    fn cmd(&mut self, cmd: &[u8]) -> Result<Option<String>> {
+        self.send_cmd(cmd)?;
+        match self.response.take() {
+            Some(response) => Ok(String::from_utf8(response).ok()),
+            None => Err(Error::NoData),
+        }
+    }
+
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        Ok(HeaderFormat::Can11)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.response = None;
+
+        // drop any frames that arrived since the last command
+        while self.read_frame(time::Duration::from_millis(10))?.is_some() {}
+
+        Ok(())
+    }
+
+    fn send_cmd(&mut self, data: &[u8]) -> Result<()> {
+        trace!("send_cmd: sending {:?}", data);
+
+        // requests to the functional address must fit in a single frame
+        let mut sender = Sender::new(self.config.clone(), data)?;
+        let frame = sender.next_frame();
+        match frame {
+            Some(frame) if sender.is_complete() => {
+                self.response = None;
+                self.write_frame(FUNCTIONAL_REQUEST_ID, &frame)?;
+            }
+            _ => {
+                return Err(Error::Communication(format!(
+                    "send_cmd: request {:?} does not fit in a single frame",
+                    data
+                )))
+            }
+        }
+
+        self.response = self.receive_responses(RESPONSE_TIMEOUT)?;
+        Ok(())
+    }
+}
+
+impl Obd2Reader for SocketCan {
+    fn get_line(&mut self) -> Result<Option<Vec<u8>>> {
+        let Some(response) = self.response.as_mut() else {
+            return Ok(None);
+        };
+
+        match response.iter().position(|b| *b == b'\n') {
+            Some(idx) => {
+                let rest = response.split_off(idx + 1);
+                let mut line = std::mem::replace(response, rest);
+                line.pop();
+                Ok(Some(line))
+            }
+            None => Ok(self.response.take()),
+        }
+    }
+
+    /// Get the rest of the response to the last command
+    ///
+    /// If the whole response was already read, this waits for more responses, like the ones that
+    /// follow an ECU's "response pending".
+    fn get_response(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.response.take() {
+            Some(response) => Ok(Some(response)),
+            None => self.receive_responses(PENDING_TIMEOUT),
+        }
+    }
+}
+
+impl SocketCan {
+    /// Open a SocketCAN interface by name, like `can0` or `vcan0`
+    ///
+    /// The interface must already be up and configured with the vehicle's bitrate (usually
+    /// 500 kbit/s).
+    pub fn new(interface: &str) -> Result<Self> {
+        Self::with_config(interface, isotp::Config::default())
+    }
+
+    /// Open a SocketCAN interface by name, with non-default ISO-TP parameters
+    pub fn with_config(interface: &str, config: isotp::Config) -> Result<Self> {
+        let socket = CanSocket::open(interface)?;
+        socket.set_filters(&[CanFilter::new(
+            FIRST_RESPONSE_ID.into(),
+            // match all of the response IDs
+            0x7ff & !(RESPONSE_ID_COUNT - 1) as u32,
+        )])?;
+
+        let mut device = SocketCan {
+            socket,
+            config,
+            response: None,
+        };
+        device.reset()?;
+
+        Ok(device)
+    }
+
+    /// Receive responses until none arrive within `timeout`, and format their frames as text
+    ///
+    /// Only the frames of complete messages are included; a message that is cut short by a
+    /// timeout or an ISO-TP error is dropped.
+    fn receive_responses(&mut self, timeout: time::Duration) -> Result<Option<Vec<u8>>> {
+        let mut receivers: HashMap<u16, Receiver> = HashMap::new();
+        // the frames of each ECU's message in progress
+        let mut pending: HashMap<u16, Vec<String>> = HashMap::new();
+        let mut frames = Vec::new();
+        let mut complete = 0;
+
+        let start = time::Instant::now();
+        let mut last_frame = time::Instant::now();
+        let mut timeout = timeout;
+        while start.elapsed() < TOTAL_TIMEOUT {
+            let remaining = timeout.saturating_sub(last_frame.elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+
+            let Some((id, data)) = self.read_frame(remaining)? else {
+                continue;
+            };
+            if !(FIRST_RESPONSE_ID..FIRST_RESPONSE_ID + RESPONSE_ID_COUNT).contains(&id) {
+                continue;
+            }
+            last_frame = time::Instant::now();
+            timeout = RESPONSE_TIMEOUT;
+
+            let receiver = receivers
+                .entry(id)
+                .or_insert_with(|| Receiver::new(self.config.clone()));
+            let message = pending.entry(id).or_default();
+            if matches!(
+                isotp::Frame::decode(&data, self.config.addressing),
+                Ok(isotp::Frame::Single(_) | isotp::Frame::First { .. })
+            ) {
+                // a new message replaces any in progress
+                message.clear();
+            }
+            match receiver.on_frame(&data) {
+                Ok(status) => {
+                    message.push(format!("{:03X} {}", id, format_bytes(&data)));
+                    match status {
+                        ReceiveStatus::Complete(response) => {
+                            debug!("receive_responses: got {:X} {:?}", id, response);
+                            frames.append(message);
+                            complete += 1;
+                        }
+                        ReceiveStatus::FlowControl(frame) => {
+                            self.write_frame(id - PHYSICAL_REQUEST_OFFSET, &frame)?
+                        }
+                        ReceiveStatus::Incomplete => (),
+                    }
+                }
+                Err(e) => {
+                    debug!("receive_responses: dropping message from {:X}: {}", id, e);
+                    message.clear();
+                }
+            }
+
+            for (id, receiver) in receivers.iter_mut() {
+                if receiver.timed_out() {
+                    debug!("receive_responses: N_Cr timeout for {:X}", id);
+                    receiver.reset();
+                    pending.remove(id);
+                }
+            }
+        }
+
+        let incomplete = receivers.values().filter(|r| r.is_receiving()).count();
+        if incomplete > 0 {
+            debug!(
+                "receive_responses: {} incomplete responses dropped",
+                incomplete
+            );
+        }
+
+        if complete == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(frames.join("\n").into_bytes()))
+    }
+
+    fn read_frame(&mut self, timeout: time::Duration) -> Result<Option<(u16, Vec<u8>)>> {
+        match self.socket.read_frame_timeout(timeout) {
+            Ok(CanFrame::Data(frame)) => match frame.id() {
+                Id::Standard(id) => {
+                    trace!("read_frame: got {:X} {:?}", id.as_raw(), frame.data());
+                    Ok(Some((id.as_raw(), frame.data().to_vec())))
+                }
+                Id::Extended(_) => Ok(None),
+            },
+            Ok(_) => Ok(None),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_frame(&mut self, id: u16, data: &[u8]) -> Result<()> {
+        let frame = StandardId::new(id)
+            .and_then(|id| CanFrame::new(id, data))
+            .ok_or_else(|| {
+                Error::Communication(format!("write_frame: invalid frame {:X} {:?}", id, data))
+            })?;
+
+        trace!("write_frame: sending {:X} {:?}", id, data);
+        let start = time::Instant::now();
+        self.socket.write_frame_insist(&frame)?;
+        if start.elapsed() > self.config.n_as {
+            return Err(isotp::Error::Timeout("N_As").into());
+        }
+        Ok(())
+    }
+}
+
+fn format_bytes(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
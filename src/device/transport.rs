@@ -0,0 +1,62 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+use super::{Error, Result};
+
+/// A byte stream that an [Elm327](super::Elm327) can communicate over
+///
+/// Reads must not block forever: if no data arrives within a short timeout, a read should return
+/// `Ok(0)` or an error of kind [TimedOut](std::io::ErrorKind::TimedOut) or
+/// [WouldBlock](std::io::ErrorKind::WouldBlock). Longer timeouts are handled by the device.
+///
+/// Only [Read] and [Write] are required to implement this trait, so that any stream can be used
+/// with an empty `impl`. Transports that have buffers or a configurable baud rate should also
+/// implement the other methods.
+pub trait Transport: Read + Write {
+    /// Discard any data waiting in the transmit and receive buffers
+    ///
+    /// The default implementation does nothing.
+    fn purge_buffers(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Change the baud rate used to communicate with the device
+    ///
+    /// The default implementation returns an error, indicating the baud rate cannot be changed.
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        Err(Error::Communication(format!(
+            "set_baud_rate: cannot change baud rate to {} on this transport",
+            baud_rate
+        )))
+    }
+}
+
+#[cfg(feature = "ftdi")]
+impl Transport for ftdi::Device {
+    fn purge_buffers(&mut self) -> Result<()> {
+        self.usb_purge_buffers()?;
+        Ok(())
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        ftdi::Device::set_baud_rate(self, baud_rate)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serialport")]
+impl Transport for Box<dyn serialport::SerialPort> {
+    fn purge_buffers(&mut self) -> Result<()> {
+        self.clear(serialport::ClearBuffer::All)?;
+        Ok(())
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        serialport::SerialPort::set_baud_rate(self.as_mut(), baud_rate)?;
+        Ok(())
+    }
+}
+
+impl Transport for TcpStream {}
@@ -0,0 +1,29 @@
+use std::fmt;
+
+/// The address of an ECU that sent a response
+///
+/// This is taken from the headers of the response, so its form depends on the protocol used by
+/// the vehicle. If the device does not report headers, responses are numbered in the order they
+/// arrived instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EcuAddress {
+    /// An 11-bit CAN ID (ISO 15765-4), like `0x7E8` for the engine control module
+    Can11(u16),
+    /// A 29-bit CAN ID (ISO 15765-4), like `0x18DAF110`; the source address is the lowest byte
+    Can29(u32),
+    /// The source address byte of a J1850, ISO 9141-2, or ISO 14230-4 header, like `0x10`
+    Legacy(u8),
+    /// The index of a response whose address is not known
+    Unknown(usize),
+}
+
+impl fmt::Display for EcuAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EcuAddress::Can11(id) => write!(f, "{:03X}", id),
+            EcuAddress::Can29(id) => write!(f, "{:08X}", id),
+            EcuAddress::Legacy(address) => write!(f, "{:02X}", address),
+            EcuAddress::Unknown(idx) => write!(f, "#{}", idx),
+        }
+    }
+}
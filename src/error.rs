@@ -5,20 +5,227 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     /// An error occurred in the [Odb2BaseDevice](crate::device::Obd2BaseDevice)
     #[error("Device error: `{0:?}`")]
-    Device(DeviceError),
+    Device(#[source] DeviceError),
 
     /// Some part of the response (described by the `&str`) was not the expected length
     #[error("Incorrect length (`{0}`): expected `{1}`, got `{2}`")]
     IncorrectResponseLength(&'static str, usize, usize),
 
+    /// The mode of a response did not match the request: the expected mode byte (with `0x40`
+    /// added) and the raw response
+    #[error("Mode mismatch: expected `{0:02X}`, got response `{1:02X?}`")]
+    ModeMismatch(u8, Vec<u8>),
+
+    /// The PID of a response did not match the request: the expected PID and the raw response
+    #[error("PID mismatch: expected `{0:02X}`, got response `{1:02X?}`")]
+    PidMismatch(u8, Vec<u8>),
+
+    /// A line of a multi-line response had the wrong index: the expected index and the raw
+    /// response
+    #[error("Bad frame index: expected `{0:X}`, got response `{1:?}`")]
+    BadFrameIndex(u8, String),
+
+    /// A response did not have the expected format, described by the `&str`; the raw response is
+    /// included
+    #[error("Unexpected frame (`{0}`): got response `{1:02X?}`")]
+    UnexpectedFrame(&'static str, Vec<u8>),
+
+    /// Every ECU that responded rejected the request with a negative response (`7F`)
+    #[error("Negative response: {0}")]
+    NegativeResponse(NegativeResponse),
+
+    /// No ECU lists the PID as supported, so it was not requested: the service and the PID
+    #[error("Unsupported: no ECU supports service `{0:02X}` PID `{1:02X}`")]
+    Unsupported(u8, u8),
+
     /// Another error occurred
     #[error("Other OBD2 error: `{0}`")]
     Other(String),
 }
 
+impl Error {
+    /// Get the underlying [device error](crate::device::Error), if this is one
+    ///
+    /// This can be used to tell apart failures like an unsupported request
+    /// ([NoData](crate::device::Error::NoData)) and a bus fault
+    /// ([CanError](crate::device::Error::CanError)).
+    pub fn device_error(&self) -> Option<&crate::device::Error> {
+        match self {
+            Error::Device(DeviceError(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Whether this error means that no ECU supports the request
+    pub(crate) fn is_unsupported(&self) -> bool {
+        matches!(self, Error::NegativeResponse(_) | Error::Unsupported(..))
+            || matches!(self.device_error(), Some(crate::device::Error::NoData))
+    }
+}
+
+/// A negative response (`7F <service> <code>`) from an ECU that rejected a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegativeResponse {
+    /// The service (mode) of the rejected request
+    pub service: u8,
+    /// Why the request was rejected
+    pub code: NegativeResponseCode,
+}
+
+impl NegativeResponse {
+    /// Decode a response, if it is a negative response
+    pub(crate) fn from_response(response: &[u8]) -> Option<Self> {
+        match *response {
+            [0x7F, service, code] => Some(NegativeResponse {
+                service,
+                code: code.into(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for NegativeResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "service `{:02X}` rejected with `{:?}` (`{:02X}`)",
+            self.service,
+            self.code,
+            u8::from(self.code)
+        )
+    }
+}
+
+macro_rules! response_codes {
+    ($($(#[$attr:meta])* $name:ident = $value:expr,)*) => {
+        /// A negative response code (NRC), as defined by ISO 14229-1 and SAE J1979
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum NegativeResponseCode {
+            $($(#[$attr])* $name,)*
+            /// A code that is reserved or manufacturer specific
+            Other(u8),
+        }
+
+        impl From<u8> for NegativeResponseCode {
+            fn from(code: u8) -> Self {
+                match code {
+                    $($value => NegativeResponseCode::$name,)*
+                    code => NegativeResponseCode::Other(code),
+                }
+            }
+        }
+
+        impl From<NegativeResponseCode> for u8 {
+            fn from(code: NegativeResponseCode) -> Self {
+                match code {
+                    $(NegativeResponseCode::$name => $value,)*
+                    NegativeResponseCode::Other(code) => code,
+                }
+            }
+        }
+    };
+}
+
+response_codes! {
+    /// The request was rejected for an unspecified reason (`0x10`)
+    GeneralReject = 0x10,
+    /// The service is not supported by the ECU (`0x11`)
+    ServiceNotSupported = 0x11,
+    /// The sub-function or PID is not supported by the ECU (`0x12`)
+    SubFunctionNotSupported = 0x12,
+    /// The request was the wrong length or had an invalid format (`0x13`)
+    IncorrectMessageLengthOrInvalidFormat = 0x13,
+    /// The response would be longer than the transport layer allows (`0x14`)
+    ResponseTooLong = 0x14,
+    /// The ECU is busy; the request should be repeated later (`0x21`)
+    BusyRepeatRequest = 0x21,
+    /// The ECU is not in a state to perform the request, like an engine that is running (`0x22`)
+    ConditionsNotCorrect = 0x22,
+    /// The request was sent in the wrong order (`0x24`)
+    RequestSequenceError = 0x24,
+    /// A sub-component needed for the request did not respond (`0x25`)
+    NoResponseFromSubnetComponent = 0x25,
+    /// A failure prevents the request from being performed (`0x26`)
+    FailurePreventsExecutionOfRequestedAction = 0x26,
+    /// A parameter of the request was out of range (`0x31`)
+    RequestOutOfRange = 0x31,
+    /// The ECU is locked (`0x33`)
+    SecurityAccessDenied = 0x33,
+    /// The security key sent did not match (`0x35`)
+    InvalidKey = 0x35,
+    /// Too many invalid security keys were sent (`0x36`)
+    ExceededNumberOfAttempts = 0x36,
+    /// A security access request was sent before a delay expired (`0x37`)
+    RequiredTimeDelayNotExpired = 0x37,
+    /// A download or upload could not be started (`0x70`)
+    UploadDownloadNotAccepted = 0x70,
+    /// A data transfer was stopped because of a fault (`0x71`)
+    TransferDataSuspended = 0x71,
+    /// Programming memory failed (`0x72`)
+    GeneralProgrammingFailure = 0x72,
+    /// A data transfer had the wrong block sequence counter (`0x73`)
+    WrongBlockSequenceCounter = 0x73,
+    /// The request was received, but the response will take longer (`0x78`)
+    ResponsePending = 0x78,
+    /// The sub-function is not supported in the active session (`0x7E`)
+    SubFunctionNotSupportedInActiveSession = 0x7E,
+    /// The service is not supported in the active session (`0x7F`)
+    ServiceNotSupportedInActiveSession = 0x7F,
+    /// The engine speed is too high (`0x81`)
+    RpmTooHigh = 0x81,
+    /// The engine speed is too low (`0x82`)
+    RpmTooLow = 0x82,
+    /// The engine is running (`0x83`)
+    EngineIsRunning = 0x83,
+    /// The engine is not running (`0x84`)
+    EngineIsNotRunning = 0x84,
+    /// The engine has not been running long enough (`0x85`)
+    EngineRunTimeTooLow = 0x85,
+    /// The temperature is too high (`0x86`)
+    TemperatureTooHigh = 0x86,
+    /// The temperature is too low (`0x87`)
+    TemperatureTooLow = 0x87,
+    /// The vehicle speed is too high (`0x88`)
+    VehicleSpeedTooHigh = 0x88,
+    /// The vehicle speed is too low (`0x89`)
+    VehicleSpeedTooLow = 0x89,
+    /// The throttle or pedal position is too high (`0x8A`)
+    ThrottlePedalTooHigh = 0x8A,
+    /// The throttle or pedal position is too low (`0x8B`)
+    ThrottlePedalTooLow = 0x8B,
+    /// The transmission is not in neutral (`0x8C`)
+    TransmissionRangeNotInNeutral = 0x8C,
+    /// The transmission is not in gear (`0x8D`)
+    TransmissionRangeNotInGear = 0x8D,
+    /// The brake pedal is not pressed (`0x8F`)
+    BrakeSwitchNotClosed = 0x8F,
+    /// The shifter is not in park (`0x90`)
+    ShifterLeverNotInPark = 0x90,
+    /// The torque converter clutch is locked (`0x91`)
+    TorqueConverterClutchLocked = 0x91,
+    /// The supply voltage is too high (`0x92`)
+    VoltageTooHigh = 0x92,
+    /// The supply voltage is too low (`0x93`)
+    VoltageTooLow = 0x93,
+}
+
+/// An error from the [device](crate::device) layer
 #[derive(Debug)]
 pub struct DeviceError(crate::device::Error);
 
+impl std::fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for DeviceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
 impl From<super::device::Error> for Error {
     fn from(e: super::device::Error) -> Self {
         Error::Device(DeviceError(e))
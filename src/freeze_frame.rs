@@ -0,0 +1,148 @@
+use log::debug;
+use std::collections::BTreeMap;
+
+use crate::{
+    commands::get_supported_ids, device::HeaderFormat, EcuAddress, Error, Obd2Device, Result,
+};
+
+/// A view of a freeze frame, the service 01 data an ECU stored when a DTC was set (service 02)
+///
+/// This implements [Obd2Device] by sending each service 01 request as a service 02 request for
+/// the frame, so every getter of [Obd2DataRetrieval](crate::commands::Obd2DataRetrieval) reads
+/// the value from the freeze frame instead of the live value. Requests for other services return
+/// an error. Use [Obd2Device::freeze_frame] to create one.
+///
+/// # Example
+/// ```
+/// use obd2::{commands::Obd2DataRetrieval, Obd2Device};
+///
+/// fn print_freeze_frame(device: &mut impl Obd2Device) -> Result<(), obd2::Error> {
+///     let mut frame = device.freeze_frame(0);
+///     println!("DTC: {:?}", frame.get_freeze_frame_dtc()?);
+///     println!("RPM: {:?}", frame.get_rpm()?);
+///     println!("All PIDs: {:02X?}", frame.dump()?);
+///     Ok(())
+/// }
+/// ```
+pub struct FreezeFrame<'a, T: Obd2Device> {
+    device: &'a mut T,
+    frame: u8,
+}
+
+impl<T: Obd2Device> Obd2Device for FreezeFrame<'_, T> {
+    fn request(&mut self, request: &[u8]) -> Result<Vec<(EcuAddress, Vec<u8>)>> {
+        let &[0x01, pid] = request else {
+            return Err(Error::Other(format!(
+                "freeze frames only contain service 01 data, not {:02X?}",
+                request
+            )));
+        };
+
+        // an ECU that answers for another frame or PID is skipped, unless no other ECU answered
+        let mut mismatched = None;
+        let mut responses = Vec::new();
+        for (address, response) in self.device.request(&[0x02, pid, self.frame])? {
+            match response.as_slice() {
+                [0x42, p, f, data @ ..] if *p == pid && *f == self.frame => {
+                    let mut translated = vec![0x41, pid];
+                    translated.extend_from_slice(data);
+                    responses.push((address, translated));
+                }
+                [0x42, ..] => {
+                    debug!(
+                        "request: dropping mismatched freeze frame from ECU {}: {:02X?}",
+                        address, response
+                    );
+                    mismatched = Some(response);
+                }
+                [0x7F, 0x02, code] => responses.push((address, vec![0x7F, 0x01, *code])),
+                // let the caller report the mismatched service
+                _ => responses.push((address, response)),
+            }
+        }
+
+        match mismatched {
+            Some(response) if responses.is_empty() => {
+                Err(Error::UnexpectedFrame("freeze frame", response))
+            }
+            _ => Ok(responses),
+        }
+    }
+
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        self.device.header_format()
+    }
+}
+
+impl<'a, T: Obd2Device> FreezeFrame<'a, T> {
+    pub(crate) fn new(device: &'a mut T, frame: u8) -> Self {
+        FreezeFrame { device, frame }
+    }
+
+    /// Get the number of the frame
+    pub fn frame(&self) -> u8 {
+        self.frame
+    }
+
+    /// Read every PID stored in the frame by each ECU
+    ///
+    /// The supported PIDs are read from the frame first, then each of them is requested. The raw
+    /// data of each PID is returned, keyed by the ECU and then the PID; the PIDs that list the
+    /// supported PIDs (`0x00`, `0x20`, ...) are not included.
+    pub fn dump(&mut self) -> Result<BTreeMap<EcuAddress, BTreeMap<u8, Vec<u8>>>> {
+        let supported = get_supported_ids(self, 0x01)?;
+        let mut pids: Vec<u8> = supported.values().flatten().copied().collect();
+        pids.sort_unstable();
+        pids.dedup();
+
+        let mut result: BTreeMap<EcuAddress, BTreeMap<u8, Vec<u8>>> = BTreeMap::new();
+        for pid in pids.into_iter().filter(|p| !p.is_multiple_of(0x20)) {
+            let responses = match self.obd_command_by_ecu(0x01, pid) {
+                Ok(responses) => responses,
+                Err(e) if e.is_unsupported() => {
+                    debug!("dump: PID {:02X} not available: {}", pid, e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            for (address, data) in responses {
+                result.entry(address).or_default().insert(pid, data);
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::Obd2DataRetrieval,
+        device::{HeaderFormat, Mock},
+        Obd2,
+    };
+
+    #[test]
+    fn mismatched_ecu_is_skipped() {
+        let mut mock = Mock::new();
+        mock.headers(HeaderFormat::Can11)
+            .expect(
+                &[0x02, 0x0C, 0x00],
+                "7E8 05 42 0C 00 1A F8\n7E9 05 42 0C 01 1A F8",
+            )
+            .expect(&[0x02, 0x0D, 0x00], "7E8 04 42 0C 00 32");
+        let mut device = Obd2::new(mock);
+        let mut frame = device.freeze_frame(0);
+
+        assert_eq!(
+            frame.get_rpm_by_ecu().unwrap(),
+            [(EcuAddress::Can11(0x7E8), 1726.0)].into()
+        );
+        // with no other ECU answering, the mismatch is an error
+        assert!(matches!(
+            frame.get_speed(),
+            Err(Error::UnexpectedFrame("freeze frame", _))
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+}
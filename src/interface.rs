@@ -1,101 +1,197 @@
 use log::{debug, trace};
+use std::collections::BTreeMap;
 
-use super::{device::Obd2BaseDevice, Error, Obd2Device, Result};
+use super::{
+    commands::{get_supported_pids, SupportedPids},
+    device::{
+        isotp::{self, ReceiveStatus, Receiver},
+        HeaderFormat, Obd2BaseDevice,
+    },
+    EcuAddress, Error, NegativeResponse, NegativeResponseCode, Obd2Device, Result,
+};
 
 /// An OBD-II interface
 ///
 /// Wraps an implementer of [Obd2BaseDevice] to allow for higher-level usage of the OBD-II
 /// interface.
+///
+/// Before the first service 01 request, the [supported PIDs](Self::supported_pids) of every ECU
+/// are read and kept. Requests for a PID that no ECU supports then fail with
+/// [Error::Unsupported] right away, instead of waiting for the device to time out.
 #[derive(Default)]
 pub struct Obd2<T: Obd2BaseDevice> {
     device: T,
+    supported_pids: Option<BTreeMap<EcuAddress, SupportedPids>>,
 }
 
 impl<T: Obd2BaseDevice> Obd2Device for Obd2<T> {
-    fn obd_command(&mut self, mode: u8, pid: u8) -> Result<Vec<Vec<u8>>> {
-        let result = self.command(&[mode, pid])?;
-
-        for response in result.iter() {
-            if response.first() != Some(&(0x40 | mode)) {
-                // mismatch of mode in response
-                todo!()
-            }
-            if response.get(1) != Some(&pid) {
-                // mismatch of PID in response
-                todo!()
-            }
-        }
-
-        Ok(result.iter().map(|l| l.split_at(2).1.to_vec()).collect())
-    }
-
-    fn obd_mode_command(&mut self, mode: u8) -> Result<Vec<Vec<u8>>> {
-        let result = self.command(std::slice::from_ref(&mode))?;
-
-        for response in result.iter() {
-            if response.first() != Some(&(0x40 | mode)) {
-                todo!()
+    fn request(&mut self, request: &[u8]) -> Result<Vec<(EcuAddress, Vec<u8>)>> {
+        // the bitmaps themselves are requested while reading the supported PIDs
+        if let [0x01, pid] = *request {
+            if !pid.is_multiple_of(0x20) {
+                let supported = self.supported_pids()?;
+                if !supported.is_empty() && !supported.values().any(|s| s.contains(pid)) {
+                    return Err(Error::Unsupported(0x01, pid));
+                }
             }
         }
 
-        Ok(result.iter().map(|l| l.split_at(1).1.to_vec()).collect())
-    }
-}
-
-impl<T: Obd2BaseDevice> Obd2<T> {
-    fn command(&mut self, command: &[u8]) -> Result<Vec<Vec<u8>>> {
         let response = self
             .device
-            .cmd(command)?
+            .cmd(request)?
             .ok_or(Error::Other("no response to command".to_owned()))?;
 
         trace!(
             "Sent OBD command {:?} and got response {:?}",
-            command,
+            request,
             response
         );
 
-        let data = if response.contains("0:") {
-            vec![self.parse_command_multiline(response)?]
-        } else {
-            self.parse_command(response)?
-        };
+        let headers = self.device.header_format()?;
+        let mut messages = Vec::new();
+        let mut pending = Vec::new();
+        self.collect_messages(response, headers, &mut messages, &mut pending)?;
+
+        // an ECU that needs more time first answers with "response pending", and the real
+        // response follows later
+        while !pending.is_empty() {
+            let Some(response) = self.device.get_response()? else {
+                break;
+            };
+            let response = String::from_utf8(response)?;
+            trace!("Got more responses {:?} after response pending", response);
+            self.collect_messages(response, headers, &mut messages, &mut pending)?;
+        }
 
-        debug!("Sent OBD command {:?} and got data {:?}", command, data);
+        // the ECUs that never sent the response they announced
+        for (address, response) in pending {
+            debug!("No response from {:?} after {}", address, response);
+            messages.push((address, vec![0x7F, response.service, response.code.into()]));
+        }
+
+        debug!("Sent OBD command {:?} and got data {:?}", request, messages);
 
-        let result = data
-            .iter()
-            .map(|l| {
-                l.iter()
-                    .map(|s| u8::from_str_radix(s, 16).map_err(|e| e.into()))
-                    .collect()
+        // number the messages whose sender is not known
+        let mut unknown = 0;
+        Ok(messages
+            .into_iter()
+            .map(|(address, m)| {
+                let address = address.unwrap_or_else(|| {
+                    unknown += 1;
+                    EcuAddress::Unknown(unknown - 1)
+                });
+                (address, m)
             })
-            .collect();
+            .collect())
+    }
 
-        result
+    /// Get the header format of the device
+    ///
+    /// Devices that detect the protocol automatically only know it once the vehicle has
+    /// answered, so the [supported PIDs](Self::supported_pids) are read first if needed.
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        self.supported_pids()?;
+        Ok(self.device.header_format()?)
     }
+}
 
-    fn parse_command(&mut self, response: String) -> Result<Vec<Vec<String>>> {
-        let result: Vec<_> = response
-            .split('\n')
-            .filter_map(|l| {
-                let res: Vec<_> = l
-                    .split(' ')
-                    .filter_map(|s| {
-                        if !s.is_empty() {
-                            Some(s.to_owned())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                if !res.is_empty() {
-                    Some(res)
-                } else {
-                    None
+impl<T: Obd2BaseDevice> Obd2<T> {
+    /// Create an OBD-II interface from an already opened device
+    pub fn new(device: T) -> Self {
+        Obd2 {
+            device,
+            supported_pids: None,
+        }
+    }
+
+    /// Get the service 01 PIDs supported by each ECU
+    ///
+    /// These are read the first time they are needed, and kept until
+    /// [forget_supported_pids](Self::forget_supported_pids) is called. If no ECU responds to the
+    /// request for the first bitmap of supported PIDs, the result is empty and every PID is
+    /// requested as usual; if a later bitmap is not available, the PIDs already found are kept.
+    pub fn supported_pids(&mut self) -> Result<&BTreeMap<EcuAddress, SupportedPids>> {
+        let supported = match self.supported_pids.take() {
+            Some(supported) => supported,
+            None => match get_supported_pids(self) {
+                Ok(supported) => supported,
+                Err(e) if e.is_unsupported() => {
+                    debug!("supported_pids: could not read supported PIDs: {}", e);
+                    BTreeMap::new()
                 }
-            })
-            .collect();
+                Err(e) => return Err(e),
+            },
+        };
+        Ok(self.supported_pids.insert(supported))
+    }
+
+    /// Forget the supported PIDs, so that they are read again before the next service 01 request
+    ///
+    /// This is needed when the device is connected to a different vehicle.
+    pub fn forget_supported_pids(&mut self) {
+        self.supported_pids = None;
+    }
+
+    /// Get a reference to the underlying device
+    pub fn device(&self) -> &T {
+        &self.device
+    }
+
+    /// Get a mutable reference to the underlying device
+    pub fn device_mut(&mut self) -> &mut T {
+        &mut self.device
+    }
+
+    /// Parse a reply and add its messages to `messages`
+    ///
+    /// "Response pending" messages are added to `pending` instead, and are removed again when
+    /// the response they announced arrives.
+    fn collect_messages(
+        &mut self,
+        response: String,
+        headers: HeaderFormat,
+        messages: &mut Vec<(Option<EcuAddress>, Vec<u8>)>,
+        pending: &mut Vec<(Option<EcuAddress>, NegativeResponse)>,
+    ) -> Result<()> {
+        let parsed = match headers {
+            HeaderFormat::None if response.contains("0:") => {
+                vec![(None, self.parse_command_multiline(response)?)]
+            }
+            HeaderFormat::None => self
+                .parse_command(response)?
+                .into_iter()
+                .map(|line| (None, line))
+                .collect(),
+            HeaderFormat::Can11 | HeaderFormat::Can29 => self.parse_can(response, headers)?,
+            HeaderFormat::Legacy => self.parse_legacy(response)?,
+        };
+
+        for (address, message) in parsed {
+            match NegativeResponse::from_response(&message) {
+                // ECUs repeat "response pending" until they are done, but each sends one
+                // response
+                Some(response) if response.code == NegativeResponseCode::ResponsePending => {
+                    if !pending.iter().any(|(a, _)| *a == address) {
+                        pending.push((address, response));
+                    }
+                }
+                _ => {
+                    if let Some(idx) = pending.iter().position(|(a, _)| *a == address) {
+                        pending.remove(idx);
+                    }
+                    messages.push((address, message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_command(&mut self, response: String) -> Result<Vec<Vec<u8>>> {
+        let result = response
+            .split('\n')
+            .map(parse_hex)
+            .filter(|l| !matches!(l, Ok(l) if l.is_empty()))
+            .collect::<Result<Vec<_>>>()?;
 
         if !result.is_empty() {
             Ok(result)
@@ -104,19 +200,200 @@ impl<T: Obd2BaseDevice> Obd2<T> {
         }
     }
 
-    fn parse_command_multiline(&mut self, response: String) -> Result<Vec<String>> {
+    fn parse_command_multiline(&mut self, response: String) -> Result<Vec<u8>> {
         let mut n_idx = 0;
-        Ok(response
-            .split('\n')
-            .filter_map(|l| l.split_once(':'))
-            .flat_map(|(idx, data)| {
-                if u8::from_str_radix(idx, 16) != Ok(n_idx) {
-                    // got an invalid hex code or values were not already in the correct order
-                    todo!("Line index: {}, should be {:X}", idx, n_idx)
+        let mut result = Vec::new();
+        for (idx, data) in response.split('\n').filter_map(|l| l.split_once(':')) {
+            if u8::from_str_radix(idx.trim(), 16) != Ok(n_idx) {
+                // got an invalid hex code or values were not already in the correct order
+                return Err(Error::BadFrameIndex(n_idx, response));
+            }
+            n_idx = (n_idx + 1) % 0x10;
+            result.extend(parse_hex(data)?);
+        }
+        Ok(result)
+    }
+
+    /// Parse CAN frames with headers, and reassemble the message from each ECU
+    fn parse_can(
+        &mut self,
+        response: String,
+        headers: HeaderFormat,
+    ) -> Result<Vec<(Option<EcuAddress>, Vec<u8>)>> {
+        let mut receivers: BTreeMap<EcuAddress, Receiver> = BTreeMap::new();
+        let mut result = Vec::new();
+
+        for line in response.split('\n').filter(|l| !l.trim().is_empty()) {
+            let (address, frame) = match headers {
+                HeaderFormat::Can11 => {
+                    let (id, frame) = line.trim().split_once(' ').unwrap_or((line, ""));
+                    (
+                        EcuAddress::Can11(u16::from_str_radix(id, 16)?),
+                        parse_hex(frame)?,
+                    )
+                }
+                _ => {
+                    let line = parse_hex(line)?;
+                    match line.split_first_chunk::<4>() {
+                        Some((id, frame)) => {
+                            (EcuAddress::Can29(u32::from_be_bytes(*id)), frame.to_vec())
+                        }
+                        None => return Err(Error::UnexpectedFrame("CAN header", line)),
+                    }
                 }
-                n_idx = (n_idx + 1) % 0x10;
-                data.split_whitespace().map(|s| s.to_owned())
+            };
+
+            let receiver = receivers
+                .entry(address)
+                .or_insert_with(|| Receiver::new(isotp::Config::default()));
+            match receiver.on_frame(&frame) {
+                Ok(ReceiveStatus::Complete(message)) => result.push((Some(address), message)),
+                Ok(_) => (),
+                Err(e) => debug!("parse_can: dropping frame from {}: {}", address, e),
+            }
+        }
+
+        for (address, receiver) in receivers.iter() {
+            if receiver.is_receiving() {
+                debug!("parse_can: dropping incomplete message from {}", address);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Parse J1850, ISO 9141-2, or ISO 14230-4 messages with headers
+    fn parse_legacy(&mut self, response: String) -> Result<Vec<(Option<EcuAddress>, Vec<u8>)>> {
+        response
+            .split('\n')
+            .map(parse_hex)
+            .filter(|l| !matches!(l, Ok(l) if l.is_empty()))
+            .map(|line| match line? {
+                // the header is followed by the data and a checksum
+                line if line.len() >= 5 => Ok((
+                    Some(EcuAddress::Legacy(line[2])),
+                    line[3..line.len() - 1].to_vec(),
+                )),
+                line => Err(Error::UnexpectedFrame("legacy header", line)),
             })
-            .collect())
+            .collect()
+    }
+}
+
+/// Parse a line of space-separated hex bytes
+fn parse_hex(line: &str) -> Result<Vec<u8>> {
+    line.split_whitespace()
+        .map(|s| u8::from_str_radix(s, 16).map_err(|e| e.into()))
+        .collect()
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{commands::Obd2DataRetrieval, device::Mock};
+
+    #[test]
+    fn supported_pids_keeps_first_bitmap() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "41 00 80 00 00 01")
+            .expect(&[0x01, 0x20], "NO DATA")
+            .expect(&[0x01, 0x01], "41 01 00 07 65 00");
+        let mut device = Obd2::new(mock);
+
+        let supported = &device.supported_pids().unwrap()[&EcuAddress::Unknown(0)];
+        assert_eq!(supported.iter().collect::<Vec<_>>(), [0x01, 0x20]);
+        assert!(matches!(
+            device.get_speed(),
+            Err(Error::Unsupported(0x01, 0x0D))
+        ));
+        assert_eq!(device.get_dtc_info().unwrap()[0].dtc_count, 0);
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn supported_pids_unavailable() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x01, 0x0D], "41 0D 32");
+        let mut device = Obd2::new(mock);
+
+        assert!(device.supported_pids().unwrap().is_empty());
+        assert_eq!(device.get_speed().unwrap(), [50]);
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn multiline_out_of_order() {
+        let response =
+            "014\n1: 47 50 30 30 52 35 35\n0: 49 02 01 31 44 34\n2: 42 31 32 33 34 35 36";
+        let mut mock = Mock::new();
+        mock.expect(&[0x09, 0x02], response);
+        let mut device = Obd2::new(mock);
+
+        assert!(matches!(
+            device.get_vin(),
+            Err(Error::BadFrameIndex(0, r)) if r == response
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn response_pending_then_response() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x01, 0x0D], "7F 01 78")
+            .then("7F 01 78")
+            .then("41 0D 32");
+        let mut device = Obd2::new(mock);
+
+        assert_eq!(
+            device.request(&[0x01, 0x0D]).unwrap(),
+            [(EcuAddress::Unknown(0), vec![0x41, 0x0D, 0x32])]
+        );
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn response_pending_then_timeout() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x01, 0x0D], "7F 01 78");
+        let mut device = Obd2::new(mock);
+
+        assert!(matches!(
+            device.get_speed(),
+            Err(Error::NegativeResponse(NegativeResponse {
+                service: 0x01,
+                code: NegativeResponseCode::ResponsePending,
+            }))
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn response_pending_from_one_ecu() {
+        let mut mock = Mock::new();
+        mock.headers(HeaderFormat::Can11)
+            .expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x01, 0x0D], "7E8 03 41 0D 32\n7E9 03 7F 01 78")
+            .then("7E9 03 41 0D 30")
+            .expect(&[0x01, 0x0D], "7E9 03 7F 01 78\n7E8 03 41 0D 32");
+        let mut device = Obd2::new(mock);
+
+        assert_eq!(
+            device.get_speed_by_ecu().unwrap(),
+            [
+                (EcuAddress::Can11(0x7E8), 50),
+                (EcuAddress::Can11(0x7E9), 48)
+            ]
+            .into()
+        );
+
+        // the ECU that never answers is left out, like any other negative response
+        assert_eq!(
+            device.get_speed_by_ecu().unwrap(),
+            [(EcuAddress::Can11(0x7E8), 50)].into()
+        );
+        assert!(device.device().verify().is_ok());
     }
 }
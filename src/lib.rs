@@ -1,15 +1,18 @@
 //! Crate for communicating with OBD-II (on-board diagnostics) interfaces on cars
 //!
-//! Currently only the ELM327 is supported (many cheap USB to OBD-II devices you can buy online are
-//! compatible with the ELM327). The high-level data retrieval functions can be found in
-//! [commands::Obd2DataRetrieval].
+//! The ELM327 is supported (many cheap USB to OBD-II devices you can buy online are compatible with
+//! the ELM327). It can be connected through an FTDI converter (the `ftdi` feature), any serial port
+//! (the `serialport` feature), or any other [device::Transport]. On Linux, a CAN bus can also be
+//! used directly through SocketCAN (the `socketcan` feature). For testing without a vehicle, the
+//! `mock` feature provides a scripted `device::Mock`. The high-level data retrieval functions can
+//! be found in [commands::Obd2DataRetrieval].
 //!
 //! # Usage
-//! ```
+//! ```no_run
 //! use obd2::{commands::Obd2DataRetrieval, device::Elm327, Obd2};
 //!
 //! fn main() -> Result<(), obd2::Error> {
-//!     let mut device = Obd2::<Elm327>::default();
+//!     let mut device = Obd2::new(Elm327::from_serial_port("/dev/ttyUSB0", 38400)?);
 //!     println!("VIN: {}", device.get_vin()?);
 //!     Ok(())
 //! }
@@ -22,12 +25,18 @@ pub mod commands;
 
 pub mod device;
 
+mod ecu_address;
+pub use ecu_address::EcuAddress;
+
 mod error;
-pub use error::Error;
 use error::Result;
+pub use error::{Error, NegativeResponse, NegativeResponseCode};
+
+mod freeze_frame;
+pub use freeze_frame::FreezeFrame;
 
 mod interface;
 pub use interface::Obd2;
 
 mod obd2_device;
-pub use obd2_device::Obd2Device;
+pub use obd2_device::{ClearConfirmation, ControlConfirmation, Obd2Device, OnBoardTest};
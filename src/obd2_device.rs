@@ -1,20 +1,99 @@
-use crate::{Error, Result};
+use log::debug;
+use std::collections::BTreeMap;
+
+use crate::{
+    commands::OxygenSensorTestResult, device::HeaderFormat, EcuAddress, Error, FreezeFrame,
+    NegativeResponse, Result,
+};
 
 /// A higher-level API for using an OBD-II device
 pub trait Obd2Device {
+    /// Send a request and get every message of the reply, with the address of its sender
+    ///
+    /// The request is the service (mode) followed by its parameters, and each message starts
+    /// with the service byte of the reply: the requested service plus `0x40`, or `0x7F` for a
+    /// [negative response](NegativeResponse). Messages are in the order they arrived, and an ECU
+    /// may send more than one. Nothing is validated, so the other methods are usually more
+    /// convenient.
+    fn request(&mut self, request: &[u8]) -> Result<Vec<(EcuAddress, Vec<u8>)>>;
+
+    /// Get the format of the headers in the replies, which depends on the protocol
+    ///
+    /// Some requests are sent differently on CAN and on older protocols. The default
+    /// implementation returns [HeaderFormat::None], as if the protocol was not known.
+    fn header_format(&mut self) -> Result<HeaderFormat> {
+        Ok(HeaderFormat::None)
+    }
+
+    /// Send an OBD-II command with mode and PID and get the response of each ECU
+    ///
+    /// The responses are keyed by the address of the ECU that sent them. The data is decoded into
+    /// the ODB-II bytes from the vehicle and the first two bytes of the response---representing
+    /// the mode and PID the vehicle received---are validated and removed. If an ECU sends several
+    /// messages, as some ECUs using older protocols do for long responses, their data is joined in
+    /// the order they arrived.
+    ///
+    /// ECUs that reject the request with a negative response are left out. If every ECU rejects
+    /// it, [Error::NegativeResponse] is returned.
+    fn obd_command_by_ecu(&mut self, mode: u8, pid: u8) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+        self.obd_request_by_ecu(&[mode, pid])
+    }
+
+    /// Send an OBD-II request with any number of parameters and get the response of each ECU
+    ///
+    /// Like [obd_command_by_ecu](Self::obd_command_by_ecu), but for requests like service 05,
+    /// which has a TID and an oxygen sensor number. The mode and every parameter are validated and
+    /// removed from the response.
+    fn obd_request_by_ecu(&mut self, request: &[u8]) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+        positive_responses(request, self.request(request)?)
+    }
+
+    /// Send an OBD-II command with only mode and get the response of each ECU
+    ///
+    /// Like [obd_command_by_ecu](Self::obd_command_by_ecu), but only the first byte of the
+    /// response---representing the mode the vehicle received---is validated and removed.
+    fn obd_mode_command_by_ecu(&mut self, mode: u8) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+        self.obd_request_by_ecu(&[mode])
+    }
+
     /// Send an OBD-II command with mode and PID and get responses
     ///
-    /// The responses are a list with one element for each ECU that responds. The data is decoded
-    /// into the ODB-II bytes from the vehicle and the first two bytes of the
-    /// response---representing the mode and PID the vehicle received---are validated and removed.
-    fn obd_command(&mut self, mode: u8, pid: u8) -> Result<Vec<Vec<u8>>>;
+    /// The responses are a list with one element for each ECU that responds, ordered by the
+    /// address of the ECU. The data is decoded into the ODB-II bytes from the vehicle and the
+    /// first two bytes of the response---representing the mode and PID the vehicle
+    /// received---are validated and removed.
+    fn obd_command(&mut self, mode: u8, pid: u8) -> Result<Vec<Vec<u8>>> {
+        Ok(self.obd_command_by_ecu(mode, pid)?.into_values().collect())
+    }
 
     /// Send an OBD-II command with only mode and get responses
     ///
-    /// The responses are a list with one element for each ECU that responds. The data is decoded
-    /// into the ODB-II bytes from the vehicle and the first byte of the response---representing
-    /// the mode the vehicle received---is validated and removed.
-    fn obd_mode_command(&mut self, mode: u8) -> Result<Vec<Vec<u8>>>;
+    /// The responses are a list with one element for each ECU that responds, ordered by the
+    /// address of the ECU. The data is decoded into the ODB-II bytes from the vehicle and the
+    /// first byte of the response---representing the mode the vehicle received---is validated and
+    /// removed.
+    fn obd_mode_command(&mut self, mode: u8) -> Result<Vec<Vec<u8>>> {
+        Ok(self.obd_mode_command_by_ecu(mode)?.into_values().collect())
+    }
+
+    /// Send command and get the OBD-II response of each ECU as an array
+    ///
+    /// Like [obd_command_len](Self::obd_command_len), but keyed by the address of each ECU.
+    fn obd_command_len_by_ecu<const RESPONSE_LENGTH: usize>(
+        &mut self,
+        mode: u8,
+        pid: u8,
+    ) -> Result<BTreeMap<EcuAddress, [u8; RESPONSE_LENGTH]>> {
+        self.obd_command_by_ecu(mode, pid)?
+            .into_iter()
+            .map(|(address, v)| {
+                let l = v.len();
+                v.try_into()
+                    .map(|v| (address, v))
+                    .map_err(|_| Error::IncorrectResponseLength("length", RESPONSE_LENGTH, l))
+            })
+            .collect()
+    }
 
     /// Send command and get list of OBD-II responses as an array
     ///
@@ -29,14 +108,10 @@ pub trait Obd2Device {
         mode: u8,
         pid: u8,
     ) -> Result<Vec<[u8; RESPONSE_LENGTH]>> {
-        self.obd_command(mode, pid)?
-            .into_iter()
-            .map(|v| {
-                let l = v.len();
-                v.try_into()
-                    .map_err(|_| Error::IncorrectResponseLength("length", RESPONSE_LENGTH, l))
-            })
-            .collect()
+        Ok(self
+            .obd_command_len_by_ecu::<RESPONSE_LENGTH>(mode, pid)?
+            .into_values()
+            .collect())
     }
 
     /// Send command and get array of OBD-II responses with each as an array
@@ -56,4 +131,392 @@ pub trait Obd2Device {
             .try_into()
             .map_err(|_| Error::IncorrectResponseLength("count", RESPONSE_COUNT, count))
     }
+
+    /// Read the freeze frame with the given number (service 02)
+    ///
+    /// Frame 0 is the one stored for the DTC that turned on the malfunction indicator light. See
+    /// [FreezeFrame].
+    fn freeze_frame(&mut self, frame: u8) -> FreezeFrame<'_, Self>
+    where
+        Self: Sized,
+    {
+        FreezeFrame::new(self, frame)
+    }
+
+    /// Run an oxygen sensor monitoring test request (service 05)
+    ///
+    /// This gets the latest result of the test with the given TID for the oxygen sensor with the
+    /// given number, as used by the service 01 oxygen sensor PIDs. Service 05 is not used on CAN,
+    /// where the results are part of the on-board monitor tests instead.
+    fn oxygen_sensor_test(
+        &mut self,
+        tid: u8,
+        sensor_id: u8,
+    ) -> Result<BTreeMap<EcuAddress, OxygenSensorTestResult>> {
+        self.obd_request_by_ecu(&[0x05, tid, sensor_id])?
+            .into_iter()
+            .map(
+                |(address, data)| match OxygenSensorTestResult::from_data(tid, sensor_id, &data) {
+                    Some(result) => Ok((address, result)),
+                    None => Err(Error::UnexpectedFrame("oxygen sensor test result", data)),
+                },
+            )
+            .collect()
+    }
+
+    /// Ask the ECUs to run a test or control a component (service 08)
+    ///
+    /// This makes the vehicle do something, so it needs a [ControlConfirmation]. The result of
+    /// each ECU that responded is returned, with the data that followed the TID in its response.
+    /// ECUs that do not support the test usually reject it with
+    /// [RequestOutOfRange](crate::NegativeResponseCode::RequestOutOfRange), and ones that cannot
+    /// run it at the moment, for example because the engine is running, with
+    /// [ConditionsNotCorrect](crate::NegativeResponseCode::ConditionsNotCorrect). The supported
+    /// tests are listed by
+    /// [get_supported_control_tests](crate::commands::Obd2DataRetrieval::get_supported_control_tests).
+    ///
+    /// On protocols other than CAN, which are recognised by their [header
+    /// format](Self::header_format), the TID is followed by the five zero bytes they expect.
+    fn on_board_control(
+        &mut self,
+        test: OnBoardTest,
+        confirmation: ControlConfirmation,
+    ) -> Result<BTreeMap<EcuAddress, std::result::Result<Vec<u8>, NegativeResponse>>> {
+        let ControlConfirmation(()) = confirmation;
+
+        let tid = test.tid();
+        let request: &[u8] = match self.header_format()? {
+            HeaderFormat::Legacy => &[0x08, tid, 0x00, 0x00, 0x00, 0x00, 0x00],
+            _ => &[0x08, tid],
+        };
+
+        let mut result = BTreeMap::new();
+        for (address, response) in self.request(request)? {
+            let status = match NegativeResponse::from_response(&response) {
+                Some(negative) => Err(negative),
+                None if response.first() != Some(&0x48) => {
+                    return Err(Error::ModeMismatch(0x48, response))
+                }
+                None if response.get(1) != Some(&tid) => {
+                    return Err(Error::PidMismatch(tid, response))
+                }
+                None => Ok(response[2..].to_vec()),
+            };
+            debug!("on_board_control: got {:02X?} from {}", status, address);
+            result.insert(address, status);
+        }
+        Ok(result)
+    }
+
+    /// Clear the DTCs and turn off the malfunction indicator light (service 04)
+    ///
+    /// This erases much more than the DTCs, so it needs a [ClearConfirmation]. The result of each
+    /// ECU that responded is returned: ECUs may reject the request, commonly with
+    /// [ConditionsNotCorrect](crate::NegativeResponseCode::ConditionsNotCorrect) when the engine
+    /// is running.
+    fn clear_dtcs(
+        &mut self,
+        confirmation: ClearConfirmation,
+    ) -> Result<BTreeMap<EcuAddress, std::result::Result<(), NegativeResponse>>> {
+        let ClearConfirmation(()) = confirmation;
+
+        let mut result = BTreeMap::new();
+        for (address, response) in self.request(&[0x04])? {
+            let status = match NegativeResponse::from_response(&response) {
+                Some(negative) => Err(negative),
+                None if response == [0x44] => Ok(()),
+                None => return Err(Error::ModeMismatch(0x44, response)),
+            };
+            debug!("clear_dtcs: got {:?} from {}", status, address);
+            result.insert(address, status);
+        }
+        Ok(result)
+    }
+}
+
+/// A test or component that can be controlled with [Obd2Device::on_board_control] (service 08)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum OnBoardTest {
+    /// Seal the evaporative emission control system (TID 01)
+    ///
+    /// The EVAP vent is closed, so the system can be tested for leaks, such as with a smoke
+    /// machine. The ECU opens the vent again when the test times out or the vehicle is driven.
+    EvapLeakTest,
+
+    /// Any other TID, most of which are manufacturer specific
+    Other(u8),
+}
+
+impl OnBoardTest {
+    /// The TID of the test
+    pub fn tid(&self) -> u8 {
+        match self {
+            OnBoardTest::EvapLeakTest => 0x01,
+            OnBoardTest::Other(tid) => *tid,
+        }
+    }
+}
+
+impl From<u8> for OnBoardTest {
+    fn from(tid: u8) -> Self {
+        match tid {
+            0x01 => OnBoardTest::EvapLeakTest,
+            tid => OnBoardTest::Other(tid),
+        }
+    }
+}
+
+/// Confirmation that [controlling components](Obd2Device::on_board_control) is intended
+///
+/// On-board control makes the ECU operate parts of the vehicle, like closing the EVAP vent. The
+/// vehicle should be parked, and the effects of the test understood, before doing this.
+///
+/// # Example
+/// ```
+/// use obd2::{ControlConfirmation, Obd2Device, OnBoardTest};
+///
+/// fn seal_evap(device: &mut impl Obd2Device) -> Result<(), obd2::Error> {
+///     let confirmation = ControlConfirmation::operate_vehicle_components();
+///     for (ecu, result) in device.on_board_control(OnBoardTest::EvapLeakTest, confirmation)? {
+///         match result {
+///             Ok(_) => println!("{}: EVAP system sealed", ecu),
+///             Err(e) => println!("{}: not sealed, {}", ecu, e),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ControlConfirmation(());
+
+impl ControlConfirmation {
+    /// Confirm that the ECUs may operate components of the vehicle
+    pub fn operate_vehicle_components() -> Self {
+        ControlConfirmation(())
+    }
+}
+
+/// Confirmation that [clearing DTCs](Obd2Device::clear_dtcs) is intended
+///
+/// Along with the DTCs, clearing erases the freeze frames, the results of on-board monitoring
+/// tests, and the status of every readiness monitor. Until the monitors run again over the
+/// following drive cycles, the vehicle may fail an emissions inspection.
+///
+/// # Example
+/// ```
+/// use obd2::{ClearConfirmation, Obd2Device};
+///
+/// fn clear(device: &mut impl Obd2Device) -> Result<(), obd2::Error> {
+///     let confirmation = ClearConfirmation::erase_freeze_frames_and_readiness();
+///     for (ecu, result) in device.clear_dtcs(confirmation)? {
+///         match result {
+///             Ok(()) => println!("{}: cleared", ecu),
+///             Err(e) => println!("{}: not cleared, {}", ecu, e),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ClearConfirmation(());
+
+impl ClearConfirmation {
+    /// Confirm that the freeze frames, test results, and readiness status will be erased
+    pub fn erase_freeze_frames_and_readiness() -> Self {
+        ClearConfirmation(())
+    }
+}
+
+/// Validate the positive responses to a request and join the data from each ECU
+///
+/// The service byte, and the PID and other parameters if there are any, are removed from each
+/// response. Negative responses are dropped, unless every ECU sent one.
+fn positive_responses(
+    request: &[u8],
+    messages: Vec<(EcuAddress, Vec<u8>)>,
+) -> Result<BTreeMap<EcuAddress, Vec<u8>>> {
+    let mode = request[0];
+
+    let mut negative = None;
+    let mut result = BTreeMap::new();
+    for (address, response) in messages {
+        if let Some(response) = NegativeResponse::from_response(&response) {
+            debug!("Dropping negative response {} from {}", response, address);
+            negative.get_or_insert(response);
+            continue;
+        }
+
+        if response.first() != Some(&(0x40 | mode)) {
+            return Err(Error::ModeMismatch(0x40 | mode, response));
+        }
+        if let Some(&pid) = request.get(1) {
+            if response.get(1..request.len()) != Some(&request[1..]) {
+                return Err(Error::PidMismatch(pid, response));
+            }
+        }
+        result
+            .entry(address)
+            .or_insert_with(Vec::new)
+            .extend_from_slice(&response[request.len()..]);
+    }
+
+    match negative {
+        Some(response) if result.is_empty() => Err(Error::NegativeResponse(response)),
+        _ => Ok(result),
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::Obd2DataRetrieval,
+        device::{HeaderFormat, Mock},
+        NegativeResponseCode, Obd2,
+    };
+
+    #[test]
+    fn mode_mismatch() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x01, 0x0D], "42 0D 32");
+        let mut device = Obd2::new(mock);
+
+        assert!(matches!(
+            device.get_speed(),
+            Err(Error::ModeMismatch(0x41, response)) if response == [0x42, 0x0D, 0x32]
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn pid_mismatch() {
+        let mut mock = Mock::new();
+        mock.expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x01, 0x0D], "41 0C 1A F8");
+        let mut device = Obd2::new(mock);
+
+        assert!(matches!(
+            device.get_speed(),
+            Err(Error::PidMismatch(0x0D, response)) if response == [0x41, 0x0C, 0x1A, 0xF8]
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn negative_response_only_if_every_ecu_rejects() {
+        let mut mock = Mock::new();
+        mock.headers(HeaderFormat::Can11)
+            .expect(&[0x09, 0x0A], "7E8 03 7F 09 31\n7E9 03 7F 09 12")
+            .expect(&[0x09, 0x0A], "7E8 03 7F 09 31\n7E9 03 49 0A 00");
+        let mut device = Obd2::new(mock);
+
+        assert!(matches!(
+            device.obd_command_by_ecu(0x09, 0x0A),
+            Err(Error::NegativeResponse(NegativeResponse {
+                service: 0x09,
+                code: NegativeResponseCode::RequestOutOfRange,
+            }))
+        ));
+        assert_eq!(
+            device.obd_command_by_ecu(0x09, 0x0A).unwrap(),
+            [(EcuAddress::Can11(0x7E9), vec![0x00])].into()
+        );
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn clear_dtcs() {
+        let mut mock = Mock::new();
+        mock.headers(HeaderFormat::Can11)
+            .expect(&[0x04], "7E8 01 44")
+            .expect(&[0x04], "7E8 01 44\n7E9 03 7F 04 22")
+            .expect(&[0x04], "7E8 02 43 00");
+        let mut device = Obd2::new(mock);
+        let confirm = ClearConfirmation::erase_freeze_frames_and_readiness;
+
+        assert_eq!(
+            device.clear_dtcs(confirm()).unwrap(),
+            [(EcuAddress::Can11(0x7E8), Ok(()))].into()
+        );
+        assert_eq!(
+            device.clear_dtcs(confirm()).unwrap(),
+            [
+                (EcuAddress::Can11(0x7E8), Ok(())),
+                (
+                    EcuAddress::Can11(0x7E9),
+                    Err(NegativeResponse {
+                        service: 0x04,
+                        code: NegativeResponseCode::ConditionsNotCorrect,
+                    })
+                ),
+            ]
+            .into()
+        );
+        assert!(matches!(
+            device.clear_dtcs(confirm()),
+            Err(Error::ModeMismatch(0x44, response)) if response == [0x43, 0x00]
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn on_board_control() {
+        let mut mock = Mock::new();
+        mock.headers(HeaderFormat::Can11)
+            .expect(&[0x01, 0x00], "NO DATA")
+            .expect(&[0x08, 0x01], "7E8 06 48 01 00 00 00 00\n7E9 03 7F 08 22")
+            .expect(&[0x08, 0x01], "7E8 03 49 01 00")
+            .expect(&[0x08, 0x01], "7E8 03 48 02 00");
+        let mut device = Obd2::new(mock);
+        let confirm = ControlConfirmation::operate_vehicle_components;
+
+        assert_eq!(
+            device
+                .on_board_control(OnBoardTest::EvapLeakTest, confirm())
+                .unwrap(),
+            [
+                (EcuAddress::Can11(0x7E8), Ok(vec![0x00; 4])),
+                (
+                    EcuAddress::Can11(0x7E9),
+                    Err(NegativeResponse {
+                        service: 0x08,
+                        code: NegativeResponseCode::ConditionsNotCorrect,
+                    })
+                ),
+            ]
+            .into()
+        );
+        assert!(matches!(
+            device.on_board_control(OnBoardTest::EvapLeakTest, confirm()),
+            Err(Error::ModeMismatch(0x48, response)) if response == [0x49, 0x01, 0x00]
+        ));
+        assert!(matches!(
+            device.on_board_control(OnBoardTest::EvapLeakTest, confirm()),
+            Err(Error::PidMismatch(0x01, response)) if response == [0x48, 0x02, 0x00]
+        ));
+        assert!(device.device().verify().is_ok());
+    }
+
+    #[test]
+    fn on_board_control_legacy_padding() {
+        let mut mock = Mock::new();
+        mock.headers(HeaderFormat::Legacy)
+            .expect(&[0x01, 0x00], "NO DATA")
+            .expect(
+                &[0x08, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00],
+                "48 6B 10 48 01 00 00 00 00 00 0C",
+            );
+        let mut device = Obd2::new(mock);
+
+        let confirmation = ControlConfirmation::operate_vehicle_components();
+        assert_eq!(
+            device
+                .on_board_control(OnBoardTest::EvapLeakTest, confirmation)
+                .unwrap(),
+            [(EcuAddress::Legacy(0x10), Ok(vec![0x00; 5]))].into()
+        );
+        assert!(device.device().verify().is_ok());
+    }
 }
@@ -0,0 +1,109 @@
+//! Runs the real [Elm327] driver against the `obd2-sim` emulator
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, Command, Stdio},
+    time,
+};
+
+use obd2::{
+    commands::{Dtc, Obd2DataRetrieval},
+    device::{Elm327, Obd2BaseDevice},
+    EcuAddress, Error, Obd2,
+};
+
+/// A running simulator, which is stopped when dropped
+struct Simulator {
+    child: Child,
+    path: String,
+}
+
+impl Simulator {
+    fn start(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_obd2-sim"))
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let path = line
+            .trim()
+            .strip_prefix("obd2-sim: listening on ")
+            .unwrap_or_else(|| panic!("unexpected output {:?}", line))
+            .to_owned();
+
+        Simulator { child, path }
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn elm327_getters() {
+    let sim = Simulator::start(&["--dtc", "P0420"]);
+
+    let mut elm327 = Elm327::from_serial_port(&sim.path, 38400).unwrap();
+    elm327.reset().unwrap();
+    let mut device = Obd2::new(elm327);
+
+    assert_eq!(device.get_vin().unwrap(), "1D4GP00R55B123456");
+    assert_eq!(
+        device.get_dtcs_by_ecu().unwrap(),
+        [
+            (EcuAddress::Can11(0x7E8), vec![Dtc::Powertrain(0x0420)]),
+            (EcuAddress::Can11(0x7E9), vec![]),
+        ]
+        .into()
+    );
+
+    let info = device.get_dtc_info().unwrap();
+    assert!(info[0].malfunction_indicator_light);
+    assert_eq!(info[0].dtc_count, 1);
+
+    let supported = &device.supported_pids().unwrap()[&EcuAddress::Can11(0x7E8)];
+    assert!(supported.contains(0x0D));
+    assert!(!supported.contains(0x10));
+    assert!(device.get_speed().unwrap()[0] <= 100);
+    assert!(matches!(
+        device.get_air_flow_rate(),
+        Err(Error::Unsupported(0x01, 0x10))
+    ));
+}
+
+#[test]
+fn non_ascii_input() {
+    let sim = Simulator::start(&[]);
+
+    let mut port = serialport::new(&sim.path, 38400)
+        .timeout(time::Duration::from_millis(100))
+        .open()
+        .unwrap();
+    let mut exchange = |command: &str| {
+        port.write_all(command.as_bytes()).unwrap();
+        port.write_all(b"\r").unwrap();
+
+        let mut reply = Vec::new();
+        let mut byte = [0];
+        let start = time::Instant::now();
+        while !reply.ends_with(b">") && start.elapsed() < time::Duration::from_secs(5) {
+            if let Ok(1) = port.read(&mut byte) {
+                reply.push(byte[0]);
+            }
+        }
+        String::from_utf8(reply).unwrap()
+    };
+
+    assert_eq!(exchange("ATé"), "ATé\r?\r\r>");
+    assert_eq!(exchange("1é"), "1é\r?\r\r>");
+    // the simulator is still running
+    assert_eq!(exchange("ATI"), "ATI\rELM327 v1.5\r\r>");
+}